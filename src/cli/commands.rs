@@ -0,0 +1,192 @@
+use std::io::{self, Read, Write};
+use std::fs::File;
+use std::rc::Rc;
+
+use graphics::{Graphic, MorphGraphic, GroupsRaw, Subdivision, Severity, Point, LoadError};
+use render::Recorder;
+#[cfg(feature = "cairo-rs")]
+use render::{Fit, render_image};
+use scan::scan;
+use utils;
+use super::{Frame, Format, load_failure};
+
+/// Stands in for the path of graphics read from standard input
+const STDIN: &str = "<stdin>";
+
+/// Validates every graphic in the directory and returns whether all of them are free of errors.
+pub fn check(path: &str) -> io::Result<bool> {
+    let mut valid = true;
+
+    for entry in scan(path).map_err(|err| in_file(path, err))? {
+        let graphic = match entry.graphic {
+            Ok(graphic) => graphic,
+            Err(err) => {
+                valid &= load_failure(err)?;
+                continue;
+            }
+        };
+
+        for issue in graphic.check() {
+            println!("{}: {}", entry.path.to_string_lossy(), issue);
+            valid &= issue.severity() < Severity::Error;
+        }
+    }
+
+    Ok(valid)
+}
+
+fn format_point(point: Point) -> String {
+    format!("{:.4} | {:.4}", point.x(), point.y())
+}
+
+/// Prints the metrics of every graphic in the directory and returns whether all of them could be loaded.
+pub fn stats(path: &str) -> io::Result<bool> {
+    let mut valid = true;
+
+    for entry in scan(path).map_err(|err| in_file(path, err))? {
+        let graphic = match entry.graphic {
+            Ok(graphic) => graphic,
+            Err(err) => {
+                valid &= load_failure(err)?;
+                continue;
+            }
+        };
+
+        println!("{}", entry.path.to_string_lossy());
+
+        match graphic.bounding_box() {
+            Some(bounds) => println!("  bounding box: {} to {}",
+                format_point(bounds.min()), format_point(bounds.max())),
+            None => println!("  bounding box: none")
+        }
+
+        println!("  area: {:.4}", graphic.signed_area());
+
+        match graphic.centroid() {
+            Some(centroid) => println!("  centroid: {}", format_point(centroid)),
+            None => println!("  centroid: none")
+        }
+
+        println!("  perimeter: {:.4}", graphic.perimeter());
+    }
+
+    Ok(valid)
+}
+
+/// Adds the path to the message of an error.
+fn in_file(path: &str, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path, err))
+}
+
+/// Reads a graphic from the file, or from standard input for `-`.
+fn read_graphic(path: &str) -> Result<Graphic, LoadError> {
+    if path != "-" {
+        return Graphic::load(path);
+    }
+
+    let mut source = String::new();
+    io::stdin().read_to_string(&mut source).map_err(|err| LoadError::io(STDIN, err))?;
+    Graphic::parse(STDIN, &source)
+}
+
+/// Writes to the file, or to standard output for `-` or none at all.
+fn write_output(path: Option<&str>, output: &[u8]) -> io::Result<()> {
+    match path {
+        Some(path) if path != "-" => File::create(path)
+            .and_then(|mut file| file.write_all(output))
+            .map_err(|err| in_file(path, err)),
+        _ => io::stdout().write_all(output)
+    }
+}
+
+/// Loads the graphics of the frame and pairs up their beziers, along with
+/// the time to draw at. Without a target, the start is shown as it is.
+fn load_frame(frame: &Frame) -> Result<(MorphGraphic, GroupsRaw, f64), LoadError> {
+    let start = Rc::new(read_graphic(&frame.start)?);
+    let (target, t) = match frame.target {
+        Some(ref target) => (Rc::new(read_graphic(target)?), frame.t),
+        None => (start.clone(), 0.0)
+    };
+
+    let (morph, groups) = match frame.seed {
+        Some(seed) => {
+            let mut rng = MorphGraphic::seeded_rng(seed);
+            MorphGraphic::with_rng(start, target, Subdivision::default(), &mut rng)
+        },
+        None => MorphGraphic::new(start, target)
+    };
+
+    Ok((morph, groups, t))
+}
+
+/// Renders the frame as PNG and returns whether its graphics could be loaded.
+#[cfg(feature = "cairo-rs")]
+pub fn render_frame(frame: &Frame) -> io::Result<bool> {
+    let (morph, groups, t) = match load_frame(frame) {
+        Ok(loaded) => loaded,
+        Err(err) => return load_failure(err)
+    };
+
+    let image = render_image(frame.width, frame.height, &Fit::Viewbox.morph_frame(&morph),
+        |renderer| morph.draw_frame(renderer, &groups, t));
+
+    let mut output = Vec::new();
+    image.write_to_png(&mut output)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("failed to encode the image: {:?}", err)))?;
+    write_output(frame.output.as_deref(), &output)?;
+
+    Ok(true)
+}
+
+/// Writes the drawing commands of the frame and returns whether its graphics could be loaded.
+pub fn export_frame(frame: &Frame) -> io::Result<bool> {
+    let (morph, groups, t) = match load_frame(frame) {
+        Ok(loaded) => loaded,
+        Err(err) => return load_failure(err)
+    };
+
+    let mut recorder = Recorder::new();
+    morph.draw_frame(&mut recorder, &groups, t);
+    write_output(frame.output.as_deref(), recorder.to_string().as_bytes())?;
+
+    Ok(true)
+}
+
+/// Lays out the files consistently and returns whether all of them are valid
+/// graphics and, if only checking, already laid out.
+pub fn format_files(options: &Format) -> io::Result<bool> {
+    let mut valid = true;
+
+    for path in &options.files {
+        let mut source = String::new();
+
+        let name = if path == "-" {
+            io::stdin().read_to_string(&mut source)?;
+            STDIN
+        } else {
+            File::open(path)
+                .and_then(|mut file| file.read_to_string(&mut source))
+                .map_err(|err| in_file(path, err))?;
+            path.as_str()
+        };
+
+        if let Err(err) = Graphic::parse(name, &source) {
+            eprintln!("{}", err);
+            valid = false;
+            continue;
+        }
+
+        let formatted = utils::reformat(&source)?;
+
+        if options.check {
+            if formatted != source {
+                println!("{}", name);
+                valid = false;
+            }
+        } else if path == "-" || formatted != source {
+            write_output(Some(path), formatted.as_bytes())?;
+        }
+    }
+
+    Ok(valid)
+}
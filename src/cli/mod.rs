@@ -0,0 +1,401 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::collections::HashMap;
+
+use graphics::LoadError;
+
+mod commands;
+
+pub use self::commands::{check, stats, export_frame, format_files};
+#[cfg(feature = "cairo-rs")]
+pub use self::commands::render_frame;
+
+pub const USAGE: &str = "\
+Usage:
+    phint [view] [DIRECTORY] [--start NAME] [--target NAME] [-t TIME]
+    phint render START [TARGET] [-t TIME] [--seed SEED] [--size WIDTHxHEIGHT] [-o FILE]
+    phint export START [TARGET] [-t TIME] [--seed SEED] [-o FILE]
+    phint check [DIRECTORY]
+    phint stats [DIRECTORY]
+    phint fmt [--check] [FILE...]
+    phint --help
+
+Commands:
+    view      Browse the graphics of the directory and morph between them
+        --start NAME            The graphic to morph from, the first one if left out
+        --target NAME           The graphic to morph into, the first one if left out
+        -t, --time TIME         Time to show between 0 and 1, the start if left out
+
+    render    Render a frame of the morph from START to TARGET as PNG
+    export    Write the drawing commands of a frame of the morph as text
+        --start FILE            The graphic to morph from, instead of START
+        --target FILE           The graphic to morph into, instead of TARGET. Without
+                                either, the start is shown on its own
+        -t, --time TIME         Time of the frame between 0 and 1, 0.5 if left out
+        --seed SEED             Pair the beziers the same way on every run
+        --size WIDTHxHEIGHT     Size of the image in pixels, or a single number for
+                                a square, 512 if left out (render only)
+        -o, --output FILE       Where to write to instead of standard output
+
+    check     Validate the graphics of the directory
+    stats     Print metrics of the graphics of the directory
+
+    fmt       Lay out graphic files consistently, in place
+        --check                 Only list the files which are not laid out yet
+
+    -h, --help                  Print this help
+
+The directory defaults to `data`. A file named `-` is read from standard input
+or written to standard output, which `fmt` uses if no file is given. `view` and
+`render` are only available if phint is built with the `viewer` feature.
+
+Exit codes:
+    0    Success
+    1    A graphic is invalid or, with --check, not laid out yet
+    2    The command line is invalid
+    3    A file could not be read or written
+";
+
+pub const EXIT_SUCCESS: i32 = 0;
+/// A graphic is invalid or not laid out yet
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+/// A file could not be read or written
+pub const EXIT_IO: i32 = 3;
+
+const DEFAULT_DIRECTORY: &str = "data";
+const DEFAULT_SIZE: i32 = 512;
+const DEFAULT_TIME: f64 = 0.5;
+
+/// What the binary is asked to do.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    View(View),
+    Render(Frame),
+    Export(Frame),
+    Check(String),
+    Stats(String),
+    Format(Format),
+    Help
+}
+
+/// Opens the viewer on a directory, optionally choosing graphics by name.
+#[derive(Clone, PartialEq, Debug)]
+pub struct View {
+    pub directory: String,
+    pub start: Option<String>,
+    pub target: Option<String>,
+    pub t: Option<f64>
+}
+
+/// A frame of the morph between two graphic files.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Frame {
+    pub start: String,
+    pub target: Option<String>,
+    pub t: f64,
+    pub seed: Option<u32>,
+    pub width: i32,
+    pub height: i32,
+    /// The file to write to, standard output if left out
+    pub output: Option<String>
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Format {
+    pub files: Vec<String>,
+    pub check: bool
+}
+
+/// A command line which does not make sense.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Returns the name of an option along with whether it takes a value.
+fn option(argument: &str) -> Option<(&'static str, bool)> {
+    match argument {
+        "-h" | "--help" => Some(("help", false)),
+        "--check" => Some(("check", false)),
+        "--start" => Some(("start", true)),
+        "--target" => Some(("target", true)),
+        "-t" | "--time" => Some(("time", true)),
+        "--seed" => Some(("seed", true)),
+        "--size" => Some(("size", true)),
+        "-o" | "--output" => Some(("output", true)),
+        _ => None
+    }
+}
+
+/// Returns the options the command accepts besides `--help`.
+fn accepted(command: &str) -> &'static [&'static str] {
+    match command {
+        "view" => &["start", "target", "time"],
+        "render" => &["start", "target", "time", "seed", "size", "output"],
+        "export" => &["start", "target", "time", "seed", "output"],
+        "fmt" => &["check"],
+        _ => &[]
+    }
+}
+
+/// Parses the arguments following the name of the binary. Without a
+/// command, the arguments are taken as those of `view`.
+pub fn parse<I: IntoIterator<Item = String>>(arguments: I) -> Result<Command, UsageError> {
+    let mut arguments = arguments.into_iter().peekable();
+
+    let command = match arguments.peek().map(String::as_str) {
+        Some("view") | Some("render") | Some("export") | Some("check") | Some("stats") | Some("fmt") =>
+            arguments.next().unwrap(),
+        _ => "view".to_string()
+    };
+
+    let mut values = HashMap::new();
+    let mut positionals = Vec::new();
+    let mut only_positionals = false;
+
+    while let Some(argument) = arguments.next() {
+        if only_positionals || argument == "-" || !argument.starts_with('-') {
+            positionals.push(argument);
+            continue;
+        }
+
+        if argument == "--" {
+            only_positionals = true;
+            continue;
+        }
+
+        // long options may also be written as `--name=value`
+        let (name, inline) = match argument.find('=') {
+            Some(index) if argument.starts_with("--") =>
+                (argument[..index].to_string(), Some(argument[index + 1..].to_string())),
+            _ => (argument.clone(), None)
+        };
+
+        let (key, takes_value) = match option(&name) {
+            Some((key, takes_value)) if key == "help" || accepted(&command).contains(&key) => (key, takes_value),
+            _ => return Err(UsageError(format!("unknown option `{}` for `{}`", name, command)))
+        };
+
+        let value = match (takes_value, inline) {
+            (true, Some(value)) => value,
+            (true, None) => arguments.next()
+                .ok_or_else(|| UsageError(format!("missing a value for `{}`", name)))?,
+            (false, Some(_)) => return Err(UsageError(format!("`{}` takes no value", name))),
+            (false, None) => String::new()
+        };
+
+        values.insert(key, value);
+    }
+
+    if values.contains_key("help") {
+        return Ok(Command::Help);
+    }
+
+    match command.as_str() {
+        "view" => Ok(Command::View(View {
+            directory: directory(positionals)?,
+            start: values.remove("start"),
+            target: values.remove("target"),
+            t: time(&values)?
+        })),
+        "render" | "export" => {
+            let mut positionals = positionals.into_iter();
+            let start = values.remove("start").or_else(|| positionals.next())
+                .ok_or_else(|| UsageError("missing the start graphic".to_string()))?;
+            let target = values.remove("target").or_else(|| positionals.next());
+
+            if let Some(argument) = positionals.next() {
+                return Err(UsageError(format!("unexpected argument `{}`", argument)));
+            }
+
+            if start == "-" && target.iter().any(|target| target == "-") {
+                return Err(UsageError("only one graphic can be read from standard input".to_string()));
+            }
+
+            let (width, height) = match values.get("size") {
+                Some(size) => parse_size(size)?,
+                None => (DEFAULT_SIZE, DEFAULT_SIZE)
+            };
+
+            let frame = Frame {
+                start, target, width, height,
+                t: time(&values)?.unwrap_or(DEFAULT_TIME),
+                seed: value(&values, "seed")?,
+                output: values.remove("output")
+            };
+
+            Ok(if command == "render" { Command::Render(frame) } else { Command::Export(frame) })
+        },
+        "check" => Ok(Command::Check(directory(positionals)?)),
+        "stats" => Ok(Command::Stats(directory(positionals)?)),
+        _ => Ok(Command::Format(Format {
+            files: if positionals.is_empty() { vec!["-".to_string()] } else { positionals },
+            check: values.contains_key("check")
+        }))
+    }
+}
+
+/// Returns the exit code for whether a command found every graphic valid,
+/// or failed to read or write a file.
+pub fn exit_code(result: &io::Result<bool>) -> i32 {
+    match *result {
+        Ok(true) => EXIT_SUCCESS,
+        Ok(false) => EXIT_FAILURE,
+        Err(_) => EXIT_IO
+    }
+}
+
+/// Reports a graphic which failed to load. A file which could not be read
+/// fails the command like any other I/O error, an invalid graphic is reported
+/// as such.
+pub fn load_failure(err: LoadError) -> io::Result<bool> {
+    if err.is_io() {
+        return Err(io::Error::other(err.to_string()));
+    }
+
+    eprintln!("{}", err);
+    Ok(false)
+}
+
+fn directory(positionals: Vec<String>) -> Result<String, UsageError> {
+    let mut positionals = positionals.into_iter();
+    let directory = positionals.next().unwrap_or_else(|| DEFAULT_DIRECTORY.to_string());
+
+    match positionals.next() {
+        Some(argument) => Err(UsageError(format!("unexpected argument `{}`", argument))),
+        None => Ok(directory)
+    }
+}
+
+fn value<T: FromStr>(values: &HashMap<&str, String>, key: &str) -> Result<Option<T>, UsageError> {
+    match values.get(key) {
+        Some(value) => value.parse().map(Some)
+            .map_err(|_| UsageError(format!("invalid value `{}` for `--{}`", value, key))),
+        None => Ok(None)
+    }
+}
+
+fn time(values: &HashMap<&str, String>) -> Result<Option<f64>, UsageError> {
+    match value::<f64>(values, "time")? {
+        Some(t) if !(0.0..=1.0).contains(&t) => Err(UsageError(format!("the time {} is not between 0 and 1", t))),
+        t => Ok(t)
+    }
+}
+
+/// Parses a size like `640x480`, or `512` for a square.
+fn parse_size(size: &str) -> Result<(i32, i32), UsageError> {
+    let invalid = || UsageError(format!("invalid size `{}`", size));
+    let mut parts = size.splitn(2, 'x');
+    let width = parts.next().unwrap_or("").parse::<i32>().map_err(|_| invalid())?;
+    let height = match parts.next() {
+        Some(height) => height.parse::<i32>().map_err(|_| invalid())?,
+        None => width
+    };
+
+    if width > 0 && height > 0 {
+        Ok((width, height))
+    } else {
+        Err(invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{self, Write};
+
+    use graphics::Graphic;
+    use utils::TestDir;
+    use super::{parse, exit_code, load_failure, Command, View, Frame, Format, EXIT_SUCCESS, EXIT_FAILURE, EXIT_IO};
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_view() {
+        let view = |directory: &str| Command::View(View {
+            directory: directory.to_string(),
+            start: None,
+            target: None,
+            t: None
+        });
+
+        assert_eq!(parse(arguments("")), Ok(view("data")));
+        assert_eq!(parse(arguments("leaves")), Ok(view("leaves")));
+        assert_eq!(parse(arguments("view leaves --start oak --target=birch -t 0.25")), Ok(Command::View(View {
+            directory: "leaves".to_string(),
+            start: Some("oak".to_string()),
+            target: Some("birch".to_string()),
+            t: Some(0.25)
+        })));
+    }
+
+    #[test]
+    fn parse_frames() {
+        assert_eq!(parse(arguments("render a.json b.json --seed 7 --size 640x480 -o out.png")), Ok(Command::Render(Frame {
+            start: "a.json".to_string(),
+            target: Some("b.json".to_string()),
+            t: 0.5,
+            seed: Some(7),
+            width: 640,
+            height: 480,
+            output: Some("out.png".to_string())
+        })));
+
+        assert_eq!(parse(arguments("export - -t 1")), Ok(Command::Export(Frame {
+            start: "-".to_string(),
+            target: None,
+            t: 1.0,
+            seed: None,
+            width: 512,
+            height: 512,
+            output: None
+        })));
+    }
+
+    #[test]
+    fn parse_other_commands() {
+        assert_eq!(parse(arguments("check")), Ok(Command::Check("data".to_string())));
+        assert_eq!(parse(arguments("stats leaves")), Ok(Command::Stats("leaves".to_string())));
+        assert_eq!(parse(arguments("fmt")), Ok(Command::Format(Format {files: vec!["-".to_string()], check: false})));
+        assert_eq!(parse(arguments("fmt --check a.json -- -b.json")), Ok(Command::Format(Format {
+            files: vec!["a.json".to_string(), "-b.json".to_string()],
+            check: true
+        })));
+        assert_eq!(parse(arguments("render --help")), Ok(Command::Help));
+        assert_eq!(parse(arguments("-h")), Ok(Command::Help));
+    }
+
+    #[test]
+    fn reject_invalid_arguments() {
+        for line in &["render", "render a b c", "render - -", "render a --size 0", "render a --size 3x",
+            "render a -t 2", "render a --seed x", "export a --size 3", "check --check", "view a b",
+            "view --time", "fmt --check=yes", "--verbose"]
+        {
+            assert!(parse(arguments(line)).is_err(), "accepted `{}`", line);
+        }
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(&Ok(true)), EXIT_SUCCESS);
+        assert_eq!(exit_code(&Ok(false)), EXIT_FAILURE);
+        assert_eq!(exit_code(&Err(io::Error::other("failed"))), EXIT_IO);
+
+        let dir = TestDir::new("exit_codes");
+        let missing = Graphic::load(dir.path().join("missing.json")).unwrap_err();
+        assert_eq!(exit_code(&load_failure(missing)), EXIT_IO);
+
+        let path = dir.path().join("invalid.json");
+        File::create(&path).unwrap().write_all(b"{").unwrap();
+        let invalid = Graphic::load(&path).unwrap_err();
+        assert_eq!(exit_code(&load_failure(invalid)), EXIT_FAILURE);
+    }
+}
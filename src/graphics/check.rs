@@ -0,0 +1,268 @@
+use std::fmt;
+
+use super::{Style, Point};
+use super::segments::{BoundingBox, Segment};
+use super::single::{Graphic, Group};
+
+const EPSILON: f64 = 1e-6;
+const SAMPLES: usize = 16;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    Warning,
+    Error
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Severity::Warning => "warning",
+            Severity::Error => "error"
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Lint {
+    NoBeziers,
+    Gap(f64),
+    ZeroLength,
+    ZeroRadius,
+    RedundantClose,
+    UnclosedFill,
+    OutsideViewport(Point)
+}
+
+impl Lint {
+    pub fn severity(&self) -> Severity {
+        match *self {
+            Lint::NoBeziers | Lint::Gap(_) | Lint::ZeroLength | Lint::ZeroRadius => Severity::Error,
+            Lint::RedundantClose | Lint::UnclosedFill | Lint::OutsideViewport(_) => Severity::Warning
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Lint::NoBeziers => f.write_str("graphic has no beziers"),
+            Lint::Gap(distance) => write!(f, "gap of {} to the previous segment", distance),
+            Lint::ZeroLength => f.write_str("segment has zero length"),
+            Lint::ZeroRadius => f.write_str("arc has zero radius"),
+            Lint::RedundantClose => f.write_str("group is closed, although its path already is"),
+            Lint::UnclosedFill => f.write_str("filled group is not closed"),
+            Lint::OutsideViewport(point) => write!(f, "point {} is outside of the viewport", point)
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Issue {
+    group: Option<usize>,
+    segment: Option<usize>,
+    lint: Lint
+}
+
+impl Issue {
+    fn new(group: Option<usize>, segment: Option<usize>, lint: Lint) -> Issue {
+        Issue {group, segment, lint}
+    }
+
+    pub fn group(&self) -> Option<usize> {
+        self.group
+    }
+
+    pub fn segment(&self) -> Option<usize> {
+        self.segment
+    }
+
+    pub fn lint(&self) -> &Lint {
+        &self.lint
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.lint.severity()
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.severity())?;
+
+        if let Some(group) = self.group {
+            write!(f, " in groups[{}]", group)?;
+        }
+
+        if let Some(segment) = self.segment {
+            write!(f, ".segments[{}]", segment)?;
+        }
+
+        write!(f, ": {}", self.lint)
+    }
+}
+
+impl Graphic {
+    /// Looks for data which is most likely broken, but only shows up visually.
+    pub fn check(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        if self.count_beziers() == 0 {
+            issues.push(Issue::new(None, None, Lint::NoBeziers));
+        }
+
+        let viewbox = self.viewbox().unwrap_or_else(BoundingBox::viewport).padded(EPSILON);
+
+        for (group_id, group) in self.groups().enumerate() {
+            check_group(group_id, group, &viewbox, &mut issues);
+        }
+
+        issues
+    }
+}
+
+fn check_group(group_id: usize, group: &Group, viewbox: &BoundingBox, issues: &mut Vec<Issue>) {
+    let mut previous: Option<&Segment> = None;
+
+    for (segment_id, segment) in group.segments().enumerate() {
+        let mut report = |lint| issues.push(Issue::new(Some(group_id), Some(segment_id), lint));
+
+        if let Some(previous) = previous {
+            let distance = previous.end().distance(segment.start());
+
+            if distance > EPSILON {
+                report(Lint::Gap(distance));
+            }
+        }
+
+        let samples = segment.samples(SAMPLES);
+
+        let zero_radius = match *segment {
+            Segment::Arc(ref arc) => arc.radius().abs() < EPSILON,
+            Segment::OvalArc(ref arc) => arc.radiusx().abs() < EPSILON || arc.radiusy().abs() < EPSILON,
+            _ => false
+        };
+
+        if zero_radius {
+            report(Lint::ZeroRadius);
+        } else if samples.iter().all(|point| point.distance(samples[0]) < EPSILON) {
+            report(Lint::ZeroLength);
+        }
+
+        let outside = samples.into_iter().find(|&point| !viewbox.contains(point));
+
+        if let Some(point) = outside {
+            report(Lint::OutsideViewport(point));
+        }
+
+        previous = Some(segment);
+    }
+
+    // after the loop, `previous` holds the last segment
+    if let (Some(first), Some(last)) = (group.segments().next(), previous) {
+        let closed = last.end().distance(first.start()) < EPSILON;
+
+        if group.close() && closed {
+            issues.push(Issue::new(Some(group_id), None, Lint::RedundantClose));
+        }
+
+        if group.style() == Style::Fill && !group.close() && !closed {
+            issues.push(Issue::new(Some(group_id), None, Lint::UnclosedFill));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graphics::{Graphic, Group, GroupBuilder, Style, Point, BoundingBox};
+    use super::{Issue, Lint};
+
+    fn check(group: Group) -> Vec<Issue> {
+        Graphic::builder().group(group).build().check()
+    }
+
+    fn triangle() -> GroupBuilder {
+        Group::builder()
+            .line((0.0, 0.0), (0.5, 0.0))
+            .line((0.5, 0.0), (0.5, 0.5))
+            .line((0.5, 0.5), (0.0, 0.0))
+    }
+
+    #[test]
+    fn clean_graphics() {
+        assert_eq!(check(triangle().build()), vec![]);
+        assert_eq!(check(triangle().style(Style::Fill).build()), vec![]);
+
+        let open = Group::builder()
+            .line((0.0, 0.0), (0.5, 0.0))
+            .arc((0.5, 0.5), 0.5, -90f64.to_radians(), 0.0)
+            .build();
+        assert_eq!(check(open), vec![]);
+    }
+
+    #[test]
+    fn no_beziers() {
+        assert_eq!(Graphic::builder().build().check(), vec![Issue::new(None, None, Lint::NoBeziers)]);
+    }
+
+    #[test]
+    fn gap() {
+        let group = Group::builder()
+            .line((0.0, 0.0), (0.5, 0.0))
+            .line((0.5, 0.25), (0.5, 0.5))
+            .build();
+
+        assert_eq!(check(group), vec![Issue::new(Some(0), Some(1), Lint::Gap(0.25))]);
+    }
+
+    #[test]
+    fn zero_length() {
+        let group = Group::builder().line((0.5, 0.5), (0.5, 0.5)).build();
+        assert_eq!(check(group), vec![Issue::new(Some(0), Some(0), Lint::ZeroLength)]);
+    }
+
+    #[test]
+    fn zero_radius() {
+        let group = Group::builder().arc((0.5, 0.5), 0.0, 0.0, 1.0).build();
+        assert_eq!(check(group), vec![Issue::new(Some(0), Some(0), Lint::ZeroRadius)]);
+
+        let group = Group::builder().oval_arc((0.5, 0.5), 0.5, 0.0, 0.0, 1.0).build();
+        assert_eq!(check(group), vec![Issue::new(Some(0), Some(0), Lint::ZeroRadius)]);
+    }
+
+    #[test]
+    fn redundant_close() {
+        assert_eq!(check(triangle().close(true).build()), vec![Issue::new(Some(0), None, Lint::RedundantClose)]);
+    }
+
+    #[test]
+    fn unclosed_fill() {
+        let group = Group::builder()
+            .style(Style::Fill)
+            .line((0.0, 0.0), (0.5, 0.0))
+            .line((0.5, 0.0), (0.5, 0.5))
+            .build();
+
+        assert_eq!(check(group.clone()), vec![Issue::new(Some(0), None, Lint::UnclosedFill)]);
+
+        let closed = Group::builder()
+            .style(Style::Fill)
+            .close(true)
+            .line((0.0, 0.0), (0.5, 0.0))
+            .line((0.5, 0.0), (0.5, 0.5))
+            .build();
+        assert_eq!(check(closed), vec![]);
+    }
+
+    #[test]
+    fn outside_viewport() {
+        let group = Group::builder().line((1.5, 0.0), (2.0, 0.0)).build();
+        let outside = Issue::new(Some(0), Some(0), Lint::OutsideViewport(Point::new(1.5, 0.0)));
+        assert_eq!(check(group.clone()), vec![outside]);
+
+        let graphic = Graphic::builder()
+            .viewbox(BoundingBox::new((-2.0, -2.0), (2.0, 2.0)))
+            .group(group)
+            .build();
+        assert_eq!(graphic.check(), vec![]);
+    }
+}
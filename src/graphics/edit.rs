@@ -0,0 +1,460 @@
+use std::f64::consts;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::Path;
+use serde_json::{self, Value};
+
+use utils;
+use super::segments::{Point, Segment, Line, Arc, OvalArc, BezierCurve};
+use super::single::Graphic;
+
+/// The parts of a segment which can be dragged around.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Handle {
+    /// The start point, which turns the start angle of arcs
+    Start,
+    /// The end point, which turns the end angle of arcs
+    End,
+    Control1,
+    Control2,
+    /// The center of an arc, which moves it as a whole
+    Center,
+    /// Sits in the middle of a circular arc
+    Radius,
+    RadiusX,
+    RadiusY
+}
+
+impl Segment {
+    /// Returns the handles of the segment together with where they are.
+    pub fn handles(&self) -> Vec<(Handle, Point)> {
+        match *self {
+            Segment::Line(ref line) => vec![(Handle::Start, line.start()), (Handle::End, line.end())],
+            Segment::Arc(ref arc) => {
+                let middle = (arc.start_angle() + arc.end_angle()) / 2.0;
+
+                vec![
+                    (Handle::Start, self.start()),
+                    (Handle::End, self.end()),
+                    (Handle::Center, arc.center()),
+                    (Handle::Radius, arc.center().on_circle(arc.radius(), middle))
+                ]
+            },
+            Segment::OvalArc(ref arc) => vec![
+                (Handle::Start, self.start()),
+                (Handle::End, self.end()),
+                (Handle::Center, arc.center()),
+                (Handle::RadiusX, arc.center() + Point::new(arc.radiusx(), 0.0)),
+                (Handle::RadiusY, arc.center() + Point::new(0.0, arc.radiusy()))
+            ],
+            Segment::BezierCurve(ref bezier) => vec![
+                (Handle::Start, bezier.start()),
+                (Handle::End, bezier.end()),
+                (Handle::Control1, bezier.control1()),
+                (Handle::Control2, bezier.control2())
+            ]
+        }
+    }
+
+    /// Returns the segment with the handle dragged to `point`, keeping the
+    /// other handles in place. Handles the segment does not have change nothing.
+    pub fn drag(&self, handle: Handle, point: Point) -> Segment {
+        match (self, handle) {
+            (&Segment::Line(ref line), Handle::Start) => Line::new(point, line.end()).into(),
+            (&Segment::Line(ref line), Handle::End) => Line::new(line.start(), point).into(),
+
+            (&Segment::Arc(ref arc), Handle::Start) => {
+                let start = follow(arc.start_angle(), point - arc.center());
+                Arc::new(arc.center(), arc.radius(), start, arc.end_angle()).into()
+            },
+            (&Segment::Arc(ref arc), Handle::End) => {
+                let end = follow(arc.end_angle(), point - arc.center());
+                Arc::new(arc.center(), arc.radius(), arc.start_angle(), end).into()
+            },
+            (&Segment::Arc(ref arc), Handle::Center) =>
+                Arc::new(point, arc.radius(), arc.start_angle(), arc.end_angle()).into(),
+            (&Segment::Arc(ref arc), Handle::Radius) =>
+                Arc::new(arc.center(), point.distance(arc.center()), arc.start_angle(), arc.end_angle()).into(),
+
+            (&Segment::OvalArc(ref arc), Handle::Start) => {
+                let start = follow(arc.start_angle(), oval_direction(arc, point));
+                OvalArc::new(arc.center(), arc.radiusx(), arc.radiusy(), start, arc.end_angle()).into()
+            },
+            (&Segment::OvalArc(ref arc), Handle::End) => {
+                let end = follow(arc.end_angle(), oval_direction(arc, point));
+                OvalArc::new(arc.center(), arc.radiusx(), arc.radiusy(), arc.start_angle(), end).into()
+            },
+            (&Segment::OvalArc(ref arc), Handle::Center) =>
+                OvalArc::new(point, arc.radiusx(), arc.radiusy(), arc.start_angle(), arc.end_angle()).into(),
+            (&Segment::OvalArc(ref arc), Handle::RadiusX) => {
+                let radiusx = (point.x() - arc.center().x()).abs();
+                OvalArc::new(arc.center(), radiusx, arc.radiusy(), arc.start_angle(), arc.end_angle()).into()
+            },
+            (&Segment::OvalArc(ref arc), Handle::RadiusY) => {
+                let radiusy = (point.y() - arc.center().y()).abs();
+                OvalArc::new(arc.center(), arc.radiusx(), radiusy, arc.start_angle(), arc.end_angle()).into()
+            },
+
+            (&Segment::BezierCurve(ref bezier), Handle::Start) =>
+                BezierCurve::new(point, bezier.control1(), bezier.control2(), bezier.end()).into(),
+            (&Segment::BezierCurve(ref bezier), Handle::Control1) =>
+                BezierCurve::new(bezier.start(), point, bezier.control2(), bezier.end()).into(),
+            (&Segment::BezierCurve(ref bezier), Handle::Control2) =>
+                BezierCurve::new(bezier.start(), bezier.control1(), point, bezier.end()).into(),
+            (&Segment::BezierCurve(ref bezier), Handle::End) =>
+                BezierCurve::new(bezier.start(), bezier.control1(), bezier.control2(), point).into(),
+
+            _ => self.clone()
+        }
+    }
+}
+
+/// Returns the angle of `direction` closest to `previous`, so that an arc
+/// does not flip around when one of its ends is dragged across the negative x axis.
+fn follow(previous: f64, direction: Point) -> f64 {
+    let turn = 2.0 * consts::PI;
+    let delta = direction.angle() - previous;
+
+    previous + delta - turn * (delta / turn).round()
+}
+
+/// Returns the direction to `point` as it would be without the stretching of the oval.
+fn oval_direction(arc: &OvalArc, point: Point) -> Point {
+    let offset = point - arc.center();
+
+    if arc.radiusx() == 0.0 || arc.radiusy() == 0.0 {
+        return offset;
+    }
+
+    Point::new(offset.x() / arc.radiusx(), offset.y() / arc.radiusy())
+}
+
+/// Refers to a handle of a segment of a graphic.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct HandleRef {
+    group: usize,
+    segment: usize,
+    handle: Handle
+}
+
+impl HandleRef {
+    pub fn new(group: usize, segment: usize, handle: Handle) -> HandleRef {
+        HandleRef {group, segment, handle}
+    }
+
+    pub fn group(&self) -> usize {
+        self.group
+    }
+
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+}
+
+/// Reshapes a graphic by dragging its handles, with a history to undo and
+/// redo whole drags.
+#[derive(Clone, Debug)]
+pub struct Editor {
+    graphic: Graphic,
+    /// The graphic as it is in its file
+    saved: Graphic,
+    undo: Vec<Graphic>,
+    redo: Vec<Graphic>,
+    dragging: bool
+}
+
+impl Editor {
+    pub fn new(graphic: Graphic) -> Editor {
+        Editor {
+            saved: graphic.clone(),
+            graphic,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            dragging: false
+        }
+    }
+
+    pub fn graphic(&self) -> &Graphic {
+        &self.graphic
+    }
+
+    /// Returns the graphic as it was when it was loaded or saved last.
+    pub fn saved(&self) -> &Graphic {
+        &self.saved
+    }
+
+    /// Returns whether the graphic differs from the one last saved.
+    pub fn is_modified(&self) -> bool {
+        self.graphic != self.saved
+    }
+
+    /// Returns every handle of the graphic together with where it is.
+    pub fn handles(&self) -> Vec<(HandleRef, Point)> {
+        let mut handles = Vec::new();
+
+        for (group_id, group) in self.graphic.groups().enumerate() {
+            for (segment_id, segment) in group.segments().enumerate() {
+                handles.extend(segment.handles().into_iter().map(|(handle, point)| {
+                    (HandleRef::new(group_id, segment_id, handle), point)
+                }));
+            }
+        }
+
+        handles
+    }
+
+    /// Returns the handle closest to `point`, if it is at most `distance` away.
+    /// Later segments win ties, as they are drawn on top.
+    pub fn handle_at(&self, point: Point, distance: f64) -> Option<HandleRef> {
+        self.handles().into_iter()
+            .map(|(handle, position)| (handle, position.distance(point)))
+            .filter(|&(_, found)| found <= distance)
+            .fold(None, |closest: Option<(HandleRef, f64)>, (handle, found)| {
+                match closest {
+                    Some((_, closest_distance)) if closest_distance < found => closest,
+                    _ => Some((handle, found))
+                }
+            })
+            .map(|(handle, _)| handle)
+    }
+
+    /// Moves the handle to `point`. All moves until `release` are undone at once.
+    pub fn drag(&mut self, handle: HandleRef, point: Point) {
+        if !self.dragging {
+            self.undo.push(self.graphic.clone());
+            self.redo.clear();
+            self.dragging = true;
+        }
+
+        let segment = self.graphic.group(handle.group).segment(handle.segment).drag(handle.handle, point);
+        self.graphic.set_segment(handle.group, handle.segment, segment);
+    }
+
+    /// Ends the current drag.
+    pub fn release(&mut self) {
+        self.dragging = false;
+    }
+
+    /// Reverts the last drag and returns whether there was one.
+    pub fn undo(&mut self) -> bool {
+        self.dragging = false;
+
+        match self.undo.pop() {
+            Some(graphic) => {
+                self.redo.push(mem::replace(&mut self.graphic, graphic));
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Repeats the last undone drag and returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        self.dragging = false;
+
+        match self.redo.pop() {
+            Some(graphic) => {
+                self.undo.push(mem::replace(&mut self.graphic, graphic));
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Writes the graphic back into the file it was loaded from. Only the values
+    /// which were changed are replaced, everything else keeps its notation.
+    /// The whole file is laid out anew like `phint fmt` does, so segments
+    /// which were written on one line are spread over several.
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut source = String::new();
+        File::open(path)?.read_to_string(&mut source)?;
+
+        let source = serde_json::from_str(&source)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let merged = merge(source, &serde_json::to_value(&self.saved)?, serde_json::to_value(&self.graphic)?);
+
+        File::create(path)?.write_all(utils::to_pretty_string(&merged)?.as_bytes())?;
+
+        self.saved = self.graphic.clone();
+        Ok(())
+    }
+}
+
+/// Carries the changes from `before` to `after` over to `source`, which was
+/// read into `before`. Whatever did not change is taken from `source`, with
+/// its notation and the fields which were left out.
+fn merge(source: Value, before: &Value, after: Value) -> Value {
+    if *before == after {
+        return source;
+    }
+
+    match (source, before, after) {
+        (Value::Object(mut source), &Value::Object(ref before), Value::Object(after)) => {
+            for key in before.keys() {
+                if !after.contains_key(key) {
+                    source.remove(key);
+                }
+            }
+
+            for (key, value) in after {
+                match before.get(&key) {
+                    Some(before) if *before == value => {},
+                    Some(before) if source.contains_key(&key) => {
+                        let field = source.get_mut(&key).unwrap();
+                        let previous = mem::replace(field, Value::Null);
+                        *field = merge(previous, before, value);
+                    },
+                    _ => {
+                        source.insert(key, value);
+                    }
+                }
+            }
+
+            Value::Object(source)
+        },
+        (Value::Array(source), &Value::Array(ref before), Value::Array(after)) => {
+            if source.len() != before.len() || before.len() != after.len() {
+                return Value::Array(after);
+            }
+
+            Value::Array(source.into_iter().zip(before).zip(after)
+                .map(|((source, before), after)| merge(source, before, after))
+                .collect())
+        },
+        (_, _, after) => after
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::f64::consts;
+    use serde_json::{self, Value};
+
+    use graphics::{Graphic, Group, Point, Segment, Line, Arc, OvalArc, BezierCurve};
+    use utils::TestDir;
+    use super::{Handle, HandleRef, Editor, merge};
+
+    fn position(segment: &Segment, handle: Handle) -> Point {
+        segment.handles().into_iter().find(|&(other, _)| other == handle).unwrap().1
+    }
+
+    #[test]
+    fn handles_follow_drags() {
+        let target = Point::new(0.1, 0.2);
+        let line: Segment = Line::new((0.0, 0.0), (1.0, 0.0)).into();
+        let bezier: Segment = BezierCurve::new((0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)).into();
+        let arc: Segment = Arc::new((0.0, 0.0), 0.5, 0.0, consts::FRAC_PI_2).into();
+        let oval: Segment = OvalArc::new((0.0, 0.0), 0.5, 0.25, 0.0, consts::PI).into();
+
+        for segment in &[line, bezier] {
+            for (handle, _) in segment.handles() {
+                assert!(position(&segment.drag(handle, target), handle).distance(target) < 1e-9);
+            }
+        }
+
+        for segment in &[arc.clone(), oval.clone()] {
+            let center = position(&segment.drag(Handle::Center, target), Handle::Center);
+            assert!(center.distance(target) < 1e-9);
+
+            // the arcs are centered at the origin, so the ends turn towards the pointer
+            for handle in &[Handle::Start, Handle::End] {
+                let position = position(&segment.drag(*handle, target), *handle);
+                assert!(position.cross(target).abs() < 1e-9 && position.dot(target) > 0.0);
+            }
+        }
+
+        assert!((position(&arc.drag(Handle::Radius, target), Handle::Radius).length() - target.length()).abs() < 1e-9);
+        assert!((position(&oval.drag(Handle::RadiusX, target), Handle::RadiusX).x() - target.x()).abs() < 1e-9);
+        assert!((position(&oval.drag(Handle::RadiusY, target), Handle::RadiusY).y() - target.y()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dragging_an_arc_end_across_the_axis_keeps_it_short() {
+        let arc: Segment = Arc::new((0.0, 0.0), 1.0, consts::FRAC_PI_2, consts::PI * 0.9).into();
+        let dragged = arc.drag(Handle::End, Point::new(-1.0, -0.1));
+
+        match dragged {
+            Segment::Arc(ref arc) => assert!(arc.end_angle() > consts::PI),
+            _ => panic!("expected an arc")
+        }
+    }
+
+    fn editor() -> Editor {
+        Editor::new(Graphic::builder()
+            .group(Group::builder().line((0.0, 0.0), (1.0, 0.0)).build())
+            .build())
+    }
+
+    #[test]
+    fn editor_undoes_whole_drags() {
+        let mut editor = editor();
+        let end = HandleRef::new(0, 0, Handle::End);
+
+        assert_eq!(editor.handle_at(Point::new(0.95, 0.0), 0.1), Some(end));
+        assert_eq!(editor.handle_at(Point::new(0.5, 0.0), 0.1), None);
+
+        editor.drag(end, Point::new(0.5, 0.5));
+        editor.drag(end, Point::new(0.5, 1.0));
+        editor.release();
+        assert!(editor.is_modified());
+
+        assert!(editor.undo());
+        assert!(!editor.is_modified());
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert_eq!(editor.graphic().group(0).segment(0).end(), Point::new(0.5, 1.0));
+        assert!(!editor.redo());
+    }
+
+    #[test]
+    fn merge_keeps_unchanged_notation() {
+        let source: Value = serde_json::from_str(r#"{
+            "comment": "kept",
+            "groups": [{"segments": [{"type": "line", "start": "1; 0°", "end": "0 | 1"}]}]
+        }"#).unwrap();
+        let before: Value = serde_json::from_str(r#"{
+            "groups": [{"segments": [{"type": "line", "start": "1 | 0", "end": "0 | 1"}], "close": false}]
+        }"#).unwrap();
+        let after: Value = serde_json::from_str(r#"{
+            "groups": [{"segments": [{"type": "line", "start": "1 | 0", "end": "0.5 | 1"}], "close": false}]
+        }"#).unwrap();
+
+        let expected: Value = serde_json::from_str(r#"{
+            "comment": "kept",
+            "groups": [{"segments": [{"type": "line", "start": "1; 0°", "end": "0.5 | 1"}]}]
+        }"#).unwrap();
+
+        assert_eq!(merge(source, &before, after), expected);
+    }
+
+    #[test]
+    fn editor_saves_into_source_file() {
+        let dir = TestDir::new("editor_saves_into_source_file");
+        let path = dir.path().join("oak.json");
+        File::create(&path).unwrap().write_all(r#"{
+            "color": [0, 0, 0],
+            "groups": [{"segments": [{"type": "line", "start": "0; 0°", "end": "1 | 0"}]}]
+        }"#.as_bytes()).unwrap();
+
+        let mut editor = Editor::new(Graphic::load(&path).unwrap());
+        editor.drag(HandleRef::new(0, 0, Handle::End), Point::new(0.5, 0.5));
+        editor.release();
+        editor.save(&path).unwrap();
+        assert!(!editor.is_modified());
+
+        let mut written = String::new();
+        File::open(&path).unwrap().read_to_string(&mut written).unwrap();
+        assert!(written.contains(r#""0; 0°""#));
+        assert!(written.contains(r#""0.5 | 0.5""#));
+        assert_eq!(&Graphic::load(&path).unwrap(), editor.graphic());
+    }
+}
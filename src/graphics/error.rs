@@ -0,0 +1,378 @@
+use std::io;
+use std::cmp;
+use std::fmt;
+use std::error;
+use std::path::{Path, PathBuf};
+use serde_json;
+
+use utils::deserialize;
+
+#[derive(Debug)]
+enum Cause {
+    Io(io::Error),
+    Json(serde_json::Error)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Location {
+    line: usize,
+    column: usize,
+    path: String,
+    offset: Option<usize>,
+    source_line: String
+}
+
+#[derive(Debug)]
+pub struct LoadError {
+    file: PathBuf,
+    message: String,
+    location: Option<Location>,
+    cause: Cause
+}
+
+impl LoadError {
+    pub fn io<P: AsRef<Path>>(file: P, err: io::Error) -> LoadError {
+        LoadError {
+            file: file.as_ref().to_owned(),
+            message: err.to_string(),
+            location: None,
+            cause: Cause::Io(err)
+        }
+    }
+
+    pub fn json<P: AsRef<Path>>(file: P, source: &str, err: serde_json::Error) -> LoadError {
+        // serde_json appends the position to its message, which we render separately
+        let suffix = format!(" at line {} column {}", err.line(), err.column());
+        let message = err.to_string();
+        let message = message.trim_end_matches(suffix.as_str()).to_owned();
+
+        let location = if err.line() > 0 {
+            let end = index_of(source, err.line(), err.column());
+            // a failure left over from deserializing something else is ignored,
+            // it only belongs to this error if the message quotes its string
+            let failure = deserialize::take_grammar_failure().and_then(|failure| {
+                if message.contains(&format!("`{}`", failure.0)) { Some(failure) } else { None }
+            });
+            let literal = failure.as_ref().map(|&(ref value, _)| value.as_str());
+            let (path, start) = locate(source, end, literal);
+
+            // point into the coordinate string if the grammar told us where it failed
+            let (index, offset) = match (start, failure) {
+                (Some(start), Some((_, offset))) => (start + 1 + offset, Some(offset)),
+                _ => (end.saturating_sub(1), None)
+            };
+
+            let (line, column) = position_of(source, index);
+            let source_line = source.lines().nth(line - 1).unwrap_or("");
+
+            Some(Location {
+                line, column, path, offset,
+                source_line: source_line.trim_end().to_owned()
+            })
+        } else {
+            None
+        };
+
+        LoadError {
+            file: file.as_ref().to_owned(),
+            message, location,
+            cause: Cause::Json(err)
+        }
+    }
+
+    /// Returns whether the file could not be read, rather than not holding a graphic.
+    pub fn is_io(&self) -> bool {
+        match self.cause {
+            Cause::Io(_) => true,
+            Cause::Json(_) => false
+        }
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.location.as_ref().map(|location| location.line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.location.as_ref().map(|location| location.column)
+    }
+
+    pub fn json_path(&self) -> Option<&str> {
+        self.location.as_ref().map(|location| location.path.as_str())
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        self.location.as_ref().and_then(|location| location.offset)
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location = match self.location {
+            Some(ref location) => location,
+            None => return write!(f, "{}: {}", self.file.display(), self.message)
+        };
+
+        writeln!(f, "{}:{}:{}: {}", self.file.display(),
+            location.line, location.column, self.message)?;
+
+        if !location.path.is_empty() {
+            writeln!(f, "  in {}", location.path)?;
+        }
+
+        // align the caret by characters, coordinates contain multi-byte degree signs
+        let gutter = location.line.to_string().len();
+        let indent = location.source_line.char_indices()
+            .take_while(|&(index, _)| index + 1 < location.column)
+            .count();
+
+        writeln!(f, "{} |", " ".repeat(gutter))?;
+        writeln!(f, "{} | {}", location.line, location.source_line)?;
+        write!(f, "{} | {}^", " ".repeat(gutter), " ".repeat(indent))
+    }
+}
+
+impl error::Error for LoadError {
+    fn description(&self) -> &str {
+        "failed to load graphic"
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match self.cause {
+            Cause::Io(ref err) => Some(err),
+            Cause::Json(ref err) => Some(err)
+        }
+    }
+}
+
+/// Converts a line and column as reported by serde_json into a byte index.
+/// The column counts the bytes consumed on that line.
+fn index_of(source: &str, line: usize, column: usize) -> usize {
+    let start: usize = source.split('\n').take(line - 1)
+        .map(|line| line.len() + 1)
+        .sum();
+
+    cmp::min(start + column, source.len())
+}
+
+/// Converts a byte index into a one-based line and column.
+fn position_of(source: &str, index: usize) -> (usize, usize) {
+    let before = &source.as_bytes()[..index];
+    let line = before.iter().filter(|&&byte| byte == b'\n').count() + 1;
+    let start = before.iter().rposition(|&byte| byte == b'\n')
+        .map_or(0, |newline| newline + 1);
+
+    (line, index - start + 1)
+}
+
+enum Scope {
+    Object(Option<String>),
+    Array(usize)
+}
+
+/// Walks the JSON source up to `end` and returns the path of the value being
+/// read there, like `groups[1].segments[0].center`.
+///
+/// Segments are buffered before they are deserialized, so errors inside them
+/// are only reported at the end of the segment. If the failing string is known,
+/// the path and start of its last occurrence before `end` are returned instead.
+fn locate(source: &str, end: usize, literal: Option<&str>) -> (String, Option<usize>) {
+    let bytes = source.as_bytes();
+    let mut scopes = Vec::new();
+    let mut found = None;
+    let mut expect_key = false;
+    let mut index = 0;
+
+    while index < end {
+        match bytes[index] {
+            b'"' => {
+                let start = index;
+                index += 1;
+
+                while index < bytes.len() && bytes[index] != b'"' {
+                    if bytes[index] == b'\\' {
+                        index += 1;
+                    }
+
+                    index += 1;
+                }
+
+                let text = &source[start + 1..cmp::min(index, bytes.len())];
+
+                match scopes.last_mut() {
+                    Some(&mut Scope::Object(ref mut key)) if expect_key => {
+                        *key = Some(text.to_owned());
+                        expect_key = false;
+                    },
+                    _ => if Some(text) == literal {
+                        found = Some((format_path(&scopes), start));
+                    }
+                }
+            },
+            b'{' => {
+                scopes.push(Scope::Object(None));
+                expect_key = true;
+            },
+            b'[' => scopes.push(Scope::Array(0)),
+            // errors about a whole object are reported right after its closing bracket
+            b'}' | b']' if index + 1 < end => {
+                scopes.pop();
+            },
+            b',' => match scopes.last_mut() {
+                Some(&mut Scope::Object(ref mut key)) => {
+                    *key = None;
+                    expect_key = true;
+                },
+                Some(&mut Scope::Array(ref mut position)) => *position += 1,
+                None => {}
+            },
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    match found {
+        Some((path, start)) => (path, Some(start)),
+        None => (format_path(&scopes), None)
+    }
+}
+
+fn format_path(scopes: &[Scope]) -> String {
+    let mut path = String::new();
+
+    for scope in scopes {
+        match *scope {
+            Scope::Object(Some(ref key)) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+
+                path.push_str(key);
+            },
+            Scope::Object(None) => {},
+            Scope::Array(position) => path.push_str(&format!("[{}]", position))
+        }
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use graphics::{Graphic, Point};
+    use super::{LoadError, Scope, index_of, position_of, locate, format_path};
+
+    const LINE_WIDTH: &str = "{
+    \"color\": [0.0, 1.0, 0.0],
+    \"groups\": [{
+        \"segments\": [],
+        \"line-width\": \"medium\"
+    }]
+}
+";
+
+    const ANGLE: &str = "{
+    \"color\": [0.0, 1.0, 0.0],
+    \"groups\": [{
+        \"segments\": [
+            {\"type\": \"line\", \"start\": \"0 | 0\", \"end\": \"1 | 0\"},
+            {\"type\": \"arc\", \"radius\": 0.3, \"start\": \"22° 30'\", \"end\": \"67° x\"}
+        ]
+    }]
+}
+";
+
+    #[test]
+    fn positions() {
+        let source = "ab\n°c\nd";
+
+        assert_eq!(index_of(source, 1, 0), 0);
+        assert_eq!(index_of(source, 2, 2), 5);
+        assert_eq!(index_of(source, 3, 5), source.len());
+
+        assert_eq!(position_of(source, 0), (1, 1));
+        assert_eq!(position_of(source, 2), (1, 3));
+        // columns count bytes, the degree sign takes two
+        assert_eq!(position_of(source, 5), (2, 3));
+        assert_eq!(position_of(source, 7), (3, 1));
+    }
+
+    #[test]
+    fn paths() {
+        let scopes = [Scope::Object(Some("groups".to_string())), Scope::Array(1),
+            Scope::Object(None), Scope::Object(Some("center".to_string()))];
+        assert_eq!(format_path(&scopes), "groups[1].center");
+        assert_eq!(format_path(&[]), "");
+
+        let end = LINE_WIDTH.find("medium").unwrap();
+        assert_eq!(locate(LINE_WIDTH, end, None), ("groups[0].line-width".to_string(), None));
+
+        // the last occurrence of the failing string before the end is taken
+        let end = ANGLE.rfind('}').unwrap();
+        let start = ANGLE.find("\"67° x\"").unwrap();
+        assert_eq!(locate(ANGLE, end, Some("67° x")),
+            ("groups[0].segments[1].end".to_string(), Some(start)));
+    }
+
+    #[test]
+    fn json_path_error() {
+        let err = Graphic::parse("test.json", LINE_WIDTH).err().unwrap();
+
+        assert_eq!(err.message(), "invalid value: string \"medium\", expected a float, `thin` or `thick`");
+        assert_eq!((err.line(), err.column()), (Some(5), Some(30)));
+        assert_eq!(err.json_path(), Some("groups[0].line-width"));
+        assert_eq!(err.offset(), None);
+        assert!(!err.is_io());
+
+        assert_eq!(err.to_string(), "\
+test.json:5:30: invalid value: string \"medium\", expected a float, `thin` or `thick`
+  in groups[0].line-width
+  |
+5 |         \"line-width\": \"medium\"
+  |                              ^");
+    }
+
+    #[test]
+    fn coordinate_error() {
+        let err = Graphic::parse("test.json", ANGLE).err().unwrap();
+
+        assert_eq!(err.message(), "invalid angle `67° x`, expected one of ., [+-], [0-9]");
+        // the column counts bytes, so both degree signs before the failure add one
+        assert_eq!((err.line(), err.column()), (Some(6), Some(78)));
+        assert_eq!(err.json_path(), Some("groups[0].segments[1].end"));
+        assert_eq!(err.offset(), Some(5));
+
+        // while the caret is aligned by characters
+        let rendered = err.to_string();
+        let caret = rendered.lines().last().unwrap();
+        assert_eq!(caret, format!("  | {}^", " ".repeat(75)));
+        assert_eq!(ANGLE.lines().nth(5).unwrap().chars().nth(75), Some('x'));
+    }
+
+    #[test]
+    fn stale_grammar_failure() {
+        // leaves a failure for the string which the next, unrelated error is about
+        assert!(serde_json::from_str::<Point>("\"medium\"").is_err());
+
+        let err = serde_json::from_str::<Graphic>(LINE_WIDTH).err().unwrap();
+        let err = LoadError::json("test.json", LINE_WIDTH, err);
+        assert_eq!((err.line(), err.column()), (Some(5), Some(30)));
+        assert_eq!(err.offset(), None);
+
+        assert!(serde_json::from_str::<Point>("\"medium\"").is_err());
+
+        let err = Graphic::parse("test.json", LINE_WIDTH).err().unwrap();
+        assert_eq!((err.line(), err.column()), (Some(5), Some(30)));
+        assert_eq!(err.offset(), None);
+    }
+}
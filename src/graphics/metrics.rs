@@ -0,0 +1,324 @@
+use std::slice;
+use std::f64::consts;
+
+use super::segments::{integrate, Point, BoundingBox, Segment, Line};
+use super::single::{Graphic, Group};
+
+const EPSILON: f64 = 1e-9;
+
+/// Integrates `f` along the segment, which gets each point with the derivative there.
+fn integrate_along<F: Fn(Point, Point) -> f64>(segment: &Segment, f: F) -> f64 {
+    match *segment {
+        Segment::Line(ref line) => {
+            let direction = line.end() - line.start();
+            integrate(|t| f(line.start() + t * direction, direction), 0.0, 1.0)
+        },
+        Segment::Arc(ref arc) =>
+            integrate_oval(arc.center(), arc.radius(), arc.radius(), arc.start_angle(), arc.end_angle(), f),
+        Segment::OvalArc(ref arc) =>
+            integrate_oval(arc.center(), arc.radiusx(), arc.radiusy(), arc.start_angle(), arc.end_angle(), f),
+        Segment::BezierCurve(ref bezier) =>
+            integrate(|t| f(bezier.point_at(t), bezier.derivative(t)), 0.0, 1.0)
+    }
+}
+
+fn integrate_oval<F>(center: Point, radiusx: f64, radiusy: f64, start: f64, end: f64, f: F) -> f64
+    where F: Fn(Point, Point) -> f64
+{
+    integrate(|angle| {
+        let (sin, cos) = angle.sin_cos();
+        f(center.on_oval(radiusx, radiusy, angle), Point::new(-radiusx * sin, radiusy * cos))
+    }, start, end)
+}
+
+/// Sums of the integrals along an outline, from which the metrics follow.
+#[derive(Default)]
+struct Moments {
+    length: f64,
+    /// Integral of the points by length, to average them
+    weighted: Point,
+    /// Twice the enclosed area, counterclockwise positive
+    area: f64,
+    /// First moments of the enclosed area, six times as large
+    moment: Point
+}
+
+impl Moments {
+    fn of(segments: &[Segment]) -> Moments {
+        let mut moments = Moments::default();
+
+        for segment in segments {
+            moments.length += segment.length();
+            moments.weighted += Point::new(
+                integrate_along(segment, |point, derivative| point.x() * derivative.length()),
+                integrate_along(segment, |point, derivative| point.y() * derivative.length()));
+            moments.area += integrate_along(segment, |point, derivative| point.cross(derivative));
+            // Green's theorem, with x² dy / 2 and -y² dx / 2 as the integrands
+            moments.moment += Point::new(
+                integrate_along(segment, |point, derivative| 3.0 * point.x() * point.x() * derivative.y()),
+                integrate_along(segment, |point, derivative| -3.0 * point.y() * point.y() * derivative.x()));
+        }
+
+        moments
+    }
+
+    fn combine(mut self, other: Moments) -> Moments {
+        self.length += other.length;
+        self.weighted += other.weighted;
+        self.area += other.area;
+        self.moment += other.moment;
+        self
+    }
+
+    /// Returns the center of the enclosed area, or of the outline if there is none.
+    fn centroid(&self) -> Option<Point> {
+        if self.area.abs() > EPSILON {
+            Some(self.moment / (3.0 * self.area))
+        } else if self.length > EPSILON {
+            Some(self.weighted / self.length)
+        } else {
+            None
+        }
+    }
+}
+
+impl Segment {
+    /// Returns the smallest axis aligned box containing the segment.
+    pub fn bounding_box(&self) -> BoundingBox {
+        let (center, radiusx, radiusy, start, end) = match *self {
+            Segment::Line(ref line) => return BoundingBox::new(line.start(), line.end()),
+            Segment::BezierCurve(ref bezier) => return bezier.bounding_box(),
+            Segment::Arc(ref arc) =>
+                (arc.center(), arc.radius(), arc.radius(), arc.start_angle(), arc.end_angle()),
+            Segment::OvalArc(ref arc) =>
+                (arc.center(), arc.radiusx(), arc.radiusy(), arc.start_angle(), arc.end_angle())
+        };
+
+        // besides the ends, the extrema lie at the multiples of a right angle
+        let (low, high) = (start.min(end), start.max(end));
+        let first = (low / consts::FRAC_PI_2).ceil() as i64;
+        let last = (high / consts::FRAC_PI_2).floor() as i64;
+
+        (first..last + 1)
+            .map(|quarter| center.on_oval(radiusx, radiusy, quarter as f64 * consts::FRAC_PI_2))
+            .fold(BoundingBox::new(self.start(), self.end()), BoundingBox::include)
+    }
+
+    /// Returns the area between the segment and the line from its end back
+    /// to its start, which is positive if they run counterclockwise.
+    pub fn signed_area(&self) -> f64 {
+        let curve = integrate_along(self, |point, derivative| point.cross(derivative));
+        (curve + self.end().cross(self.start())) / 2.0
+    }
+
+    /// Returns the center of mass of the segment as a thin wire.
+    pub fn centroid(&self) -> Point {
+        let moments = Moments::of(slice::from_ref(self));
+
+        if moments.length > EPSILON {
+            moments.weighted / moments.length
+        } else {
+            self.start()
+        }
+    }
+}
+
+impl Group {
+    /// Checks whether the path ends where it starts, or is closed explicitly.
+    pub fn is_closed(&self) -> bool {
+        let ends = (self.segments().next(), self.segments().last());
+
+        match ends {
+            (Some(first), Some(last)) => self.close() || last.end().distance(first.start()) < EPSILON,
+            _ => false
+        }
+    }
+
+    /// Returns the segments together with the lines drawn between them, which
+    /// includes the line back to the start if `close` is set.
+    fn outline(&self, close: bool) -> Vec<Segment> {
+        let mut outline: Vec<Segment> = Vec::new();
+
+        for segment in self.segments() {
+            if let Some(previous) = outline.last().map(Segment::end) {
+                if previous.distance(segment.start()) > EPSILON {
+                    outline.push(Line::new(previous, segment.start()).into());
+                }
+            }
+
+            outline.push(segment.clone());
+        }
+
+        if let (true, Some(first), Some(last)) = (close, self.segments().next(), self.segments().last()) {
+            if last.end().distance(first.start()) > EPSILON {
+                outline.push(Line::new(last.end(), first.start()).into());
+            }
+        }
+
+        outline
+    }
+
+    /// Returns the smallest axis aligned box containing the path, without the
+    /// line width, or `None` if the group is empty.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.outline(false).iter()
+            .map(Segment::bounding_box)
+            .fold(None, |bounds, next| Some(bounds.map_or(next, |bounds: BoundingBox| bounds.union(next))))
+    }
+
+    /// Returns the enclosed area, which is positive if the path runs
+    /// counterclockwise, or `None` if the group is not closed.
+    pub fn signed_area(&self) -> Option<f64> {
+        if !self.is_closed() {
+            return None;
+        }
+
+        Some(Moments::of(&self.outline(true)).area / 2.0)
+    }
+
+    /// Returns the center of the enclosed area, or of the path for open groups.
+    pub fn centroid(&self) -> Option<Point> {
+        self.moments().centroid()
+    }
+
+    /// Returns the length of the path as drawn, including the closing line.
+    pub fn perimeter(&self) -> f64 {
+        self.outline(self.close()).iter().map(Segment::length).sum()
+    }
+
+    fn moments(&self) -> Moments {
+        if self.is_closed() {
+            Moments::of(&self.outline(true))
+        } else {
+            // open groups enclose nothing
+            Moments {area: 0.0, moment: Point::default(), .. Moments::of(&self.outline(false))}
+        }
+    }
+}
+
+impl Graphic {
+    /// Returns the smallest axis aligned box containing all groups, or `None` if there are none.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.groups()
+            .filter_map(Group::bounding_box)
+            .fold(None, |bounds, next| Some(bounds.map_or(next, |bounds: BoundingBox| bounds.union(next))))
+    }
+
+    /// Returns the sum of the areas of the closed groups, where clockwise
+    /// groups count negatively, so that they can cut holes.
+    pub fn signed_area(&self) -> f64 {
+        self.groups().filter_map(Group::signed_area).sum()
+    }
+
+    /// Returns the center of the enclosed area, or of the paths if nothing is enclosed.
+    pub fn centroid(&self) -> Option<Point> {
+        self.groups()
+            .map(Group::moments)
+            .fold(Moments::default(), Moments::combine)
+            .centroid()
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        self.groups().map(Group::perimeter).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts;
+
+    use graphics::{Graphic, Group, Point, Segment, Arc, OvalArc};
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "expected {}, got {}", expected, actual);
+    }
+
+    fn assert_close(actual: Point, expected: Point) {
+        assert!(actual.distance(expected) < 1e-6, "expected {}, got {}", expected, actual);
+    }
+
+    fn square(counterclockwise: bool) -> Group {
+        let mut corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+        if !counterclockwise {
+            corners.reverse();
+        }
+
+        // leave out the last side, which is added by closing the path
+        corners.windows(2)
+            .fold(Group::builder(), |group, pair| group.line(pair[0], pair[1]))
+            .close(true)
+            .build()
+    }
+
+    fn circle(center: (f64, f64), radius: f64, counterclockwise: bool) -> Group {
+        let end = if counterclockwise { 2.0 * consts::PI } else { -2.0 * consts::PI };
+        Group::builder().arc(center, radius, 0.0, end).build()
+    }
+
+    #[test]
+    fn metrics_of_square() {
+        let group = square(true);
+        let bounds = group.bounding_box().unwrap();
+
+        assert_close(bounds.min(), Point::new(0.0, 0.0));
+        assert_close(bounds.max(), Point::new(1.0, 1.0));
+        assert_near(group.signed_area().unwrap(), 1.0);
+        assert_close(group.centroid().unwrap(), Point::new(0.5, 0.5));
+        assert_near(group.perimeter(), 4.0);
+
+        assert_near(square(false).signed_area().unwrap(), -1.0);
+    }
+
+    #[test]
+    fn metrics_of_circle() {
+        let group = circle((0.5, -0.25), 0.5, true);
+        let bounds = group.bounding_box().unwrap();
+
+        assert!(group.is_closed());
+        assert_close(bounds.min(), Point::new(0.0, -0.75));
+        assert_close(bounds.max(), Point::new(1.0, 0.25));
+        assert_near(group.signed_area().unwrap(), consts::PI / 4.0);
+        assert_close(group.centroid().unwrap(), Point::new(0.5, -0.25));
+        assert_near(group.perimeter(), consts::PI);
+    }
+
+    #[test]
+    fn metrics_of_segments() {
+        let half_circle = Segment::from(Arc::new((0.0, 0.0), 1.0, 0.0, consts::PI));
+
+        assert_near(half_circle.signed_area(), consts::FRAC_PI_2);
+        // the wire, not the half disc
+        assert_close(half_circle.centroid(), Point::new(0.0, 2.0 / consts::PI));
+
+        let oval = Segment::from(OvalArc::new((0.0, 0.0), 2.0, 1.0, 0.25, 2.0));
+        let bounds = oval.bounding_box();
+
+        assert_close(bounds.min(), Point::new(2.0 * 2f64.cos(), 0.25f64.sin()));
+        assert_close(bounds.max(), Point::new(2.0 * 0.25f64.cos(), 1.0));
+    }
+
+    #[test]
+    fn open_group_encloses_nothing() {
+        let group = Group::builder().line((0.0, 0.0), (1.0, 0.0)).line((1.0, 0.0), (1.0, 1.0)).build();
+
+        assert_eq!(group.signed_area(), None);
+        assert_close(group.centroid().unwrap(), Point::new(0.75, 0.25));
+        assert_near(group.perimeter(), 2.0);
+    }
+
+    #[test]
+    fn clockwise_groups_cut_holes() {
+        let graphic = Graphic::builder()
+            .group(circle((0.0, 0.0), 1.0, true))
+            .group(circle((0.5, 0.0), 0.25, false))
+            .build();
+
+        let area = consts::PI * (1.0 - 0.0625);
+        assert_near(graphic.signed_area(), area);
+        // the hole shifts the center away from it
+        assert_close(graphic.centroid().unwrap(), Point::new(-0.5 * consts::PI * 0.0625 / area, 0.0));
+        assert_near(graphic.perimeter(), 2.5 * consts::PI);
+        assert_eq!(Graphic::builder().build().bounding_box(), None);
+    }
+}
@@ -1,12 +1,17 @@
-use cairo::Context;
-
+mod error;
+mod check;
+mod metrics;
 mod segments;
 mod single;
 mod morph;
+mod edit;
 
-pub use self::segments::Point;
-pub use self::single::Graphic;
-pub use self::morph::MorphGraphic;
+pub use self::error::LoadError;
+pub use self::check::{Issue, Lint, Severity};
+pub use self::segments::{Point, BoundingBox, Affine, Segment, Line, Arc, OvalArc, BezierCurve};
+pub use self::single::{Graphic, Group, GraphicBuilder, GroupBuilder};
+pub use self::morph::{MorphGraphic, BezierPair, Subdivision, GroupsRaw, GroupsLinked};
+pub use self::edit::{Handle, HandleRef, Editor};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Style {
@@ -14,15 +19,6 @@ pub enum Style {
     Fill
 }
 
-impl Style {
-    fn paint(self, ctx: &Context) {
-        match self {
-            Style::Stroke => Context::stroke(ctx),
-            Style::Fill => Context::fill(ctx)
-        }
-    }
-}
-
 impl Default for Style {
     fn default() -> Style {
         Style::Stroke
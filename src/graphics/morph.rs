@@ -1,12 +1,94 @@
 use std::cmp;
 use std::rc::Rc;
-use rand::{self, Rng};
-use cairo::Context;
-
+use num::Integer;
+use rand::{self, Rng, SeedableRng, XorShiftRng};
+use render::Renderer;
 use utils::Lerp;
-use super::segments::BezierCurve;
+use super::Style;
+use super::segments::{BoundingBox, BezierCurve};
 use super::single::{Graphic, Group};
 
+/// The words which precede the seed of `MorphGraphic::seeded_rng`
+const SEED_PREFIX: [u32; 3] = [0x193a6754, 0xa8a7d469, 0x97830e05];
+
+/// Decides how often every segment gets split, so that start and target end up
+/// with the same number of beziers.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum Subdivision {
+    /// Split into the least common multiple of both bezier counts. This grows
+    /// quickly for graphics with co-prime counts.
+    #[default]
+    LeastCommonMultiple,
+    /// Split into the larger of both bezier counts, but at least into the given budget.
+    Proportional(usize)
+}
+
+impl Subdivision {
+    fn count(self, start_count: usize, target_count: usize) -> usize {
+        match self {
+            Subdivision::LeastCommonMultiple => start_count.lcm(&target_count),
+            Subdivision::Proportional(budget) =>
+                cmp::max(budget, cmp::max(start_count, target_count))
+        }
+    }
+
+    /// Returns the number of beziers for each segment of the graphic, summing up to `count`.
+    fn splits(self, graphic: &Graphic, count: usize) -> Vec<usize> {
+        let graphic_count = graphic.count_beziers();
+        let segments = graphic.groups().flat_map(Group::segments);
+
+        match self {
+            Subdivision::LeastCommonMultiple => {
+                segments.enumerate().map(|(segment_id, segment)| {
+                    let mut splits = segment.count_beziers();
+                    splits *= count / graphic_count;
+
+                    if segment_id < count % graphic_count {
+                        splits += 1;
+                    }
+
+                    splits
+                }).collect()
+            },
+            Subdivision::Proportional(_) => {
+                // every segment gets at least its own bezier count, the splits
+                // beyond that are handed out proportional to the segment lengths
+                let segments = segments.collect::<Vec<_>>();
+                let lengths = segments.iter()
+                    .map(|segment| segment.length())
+                    .map(|length| if length.is_finite() { length } else { 0.0 })
+                    .collect::<Vec<_>>();
+                let total = lengths.iter().sum::<f64>();
+                let extra = count - graphic_count;
+
+                let shares = lengths.iter().map(|&length| {
+                    if total > 0.0 {
+                        extra as f64 * length / total
+                    } else {
+                        extra as f64 / segments.len() as f64
+                    }
+                }).collect::<Vec<_>>();
+
+                let mut splits = segments.iter().zip(&shares)
+                    .map(|(segment, share)| segment.count_beziers() + share.floor() as usize)
+                    .collect::<Vec<_>>();
+
+                // rounding down loses some splits, give them to the largest remainders
+                let lost = extra - shares.iter().map(|share| share.floor() as usize).sum::<usize>();
+                let mut order = (0..segments.len()).collect::<Vec<_>>();
+                order.sort_by(|&a, &b| shares[b].fract().partial_cmp(&shares[a].fract())
+                    .unwrap_or(cmp::Ordering::Equal));
+
+                for &segment_id in order.iter().take(lost) {
+                    splits[segment_id] += 1;
+                }
+
+                splits
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct GroupRaw {
     group_id: usize,
@@ -95,9 +177,41 @@ impl MorphGraphic {
     pub fn new(start: Rc<Graphic>, target: Rc<Graphic>)
         -> (MorphGraphic, GroupsRaw)
     {
+        MorphGraphic::with_subdivision(start, target, Subdivision::default())
+    }
+
+    pub fn with_subdivision(start: Rc<Graphic>, target: Rc<Graphic>,
+        subdivision: Subdivision) -> (MorphGraphic, GroupsRaw)
+    {
+        MorphGraphic::with_rng(start, target, subdivision, &mut rand::thread_rng())
+    }
+
+    /// Returns a generator for `with_rng`, which pairs the beziers the same
+    /// way on every run with the same seed.
+    pub fn seeded_rng(seed: u32) -> XorShiftRng {
+        XorShiftRng::from_seed([SEED_PREFIX[0], SEED_PREFIX[1], SEED_PREFIX[2], seed])
+    }
+
+    /// Creates the morph between both graphics, pairing the beziers randomly
+    /// with the given generator. If one of them is empty, the other one grows
+    /// out of its center, for which the empty one gets replaced by a collapsed
+    /// copy of the other one.
+    pub fn with_rng<R: Rng>(start: Rc<Graphic>, target: Rc<Graphic>,
+        subdivision: Subdivision, rng: &mut R) -> (MorphGraphic, GroupsRaw)
+    {
+        let (start, target) = match (start.count_beziers(), target.count_beziers()) {
+            (0, 0) => {
+                let graphic = MorphGraphic {start, target, beziers: Vec::new()};
+                return (graphic, GroupsRaw(Vec::new()));
+            },
+            (0, _) => (Rc::new(target.collapse()), target),
+            (_, 0) => (start.clone(), Rc::new(start.collapse())),
+            _ => (start, target)
+        };
+
         let start_count = start.count_beziers();
         let target_count = target.count_beziers();
-        let count = start_count.lcm(&target_count);
+        let count = subdivision.count(start_count, target_count);
 
         let mut graphic = MorphGraphic {
             start: start.clone(),
@@ -106,28 +220,21 @@ impl MorphGraphic {
         };
 
         let mut groups = GroupsRaw(Vec::with_capacity(2 * count));
-        graphic.append_beziers(&start, start_count, count, &mut groups);
-        graphic.append_beziers(&target, target_count, count, &mut groups);
+        graphic.append_beziers(&start, subdivision.splits(&start, count), &mut groups, rng);
+        graphic.append_beziers(&target, subdivision.splits(&target, count), &mut groups, rng);
 
         (graphic, groups)
     }
 
-    fn append_beziers(&mut self, graphic: &Graphic,
-        graphic_count: usize, count: usize, groups: &mut GroupsRaw)
+    fn append_beziers<R: Rng>(&mut self, graphic: &Graphic,
+        splits: Vec<usize>, groups: &mut GroupsRaw, rng: &mut R)
     {
         let mut combined = Vec::new();
-        let mut segment_id = 0;
+        let mut splits = splits.into_iter();
         let mut bezier_id = 0;
 
         for (group_id, group) in graphic.groups().enumerate() {
-            for segment in group.segments() {
-                let mut splits = segment.count_beziers();
-                splits *= count / graphic_count;
-
-                if segment_id < count % graphic_count {
-                    splits += 1;
-                }
-
+            for (segment, splits) in group.segments().zip(&mut splits) {
                 let splits = segment.to_beziers(splits).into_iter()
                     .map(|bezier| {
                         bezier_id += 1;
@@ -135,11 +242,9 @@ impl MorphGraphic {
                     });
 
                 combined.extend(splits);
-                segment_id += 1;
             }
         }
 
-        let mut rng = rand::thread_rng();
         rng.shuffle(&mut combined);
 
         for (bezier, group_id, pos) in combined {
@@ -148,15 +253,107 @@ impl MorphGraphic {
         }
     }
 
-    pub fn draw<'a>(&'a self, ctx: &Context, groups: GroupsLinked<'a>, t: f64) {
+    /// Returns a box containing the start, the target and every frame in
+    /// between, or `None` if both are empty.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        // every frame lies within the control points of the beziers it lerps
+        let control_points = self.beziers.iter().flat_map(|bezier| {
+            vec![bezier.start(), bezier.control1(), bezier.control2(), bezier.end()]
+        });
+
+        let bounds = self.start.bounding_box().into_iter()
+            .chain(self.target.bounding_box())
+            .chain(control_points.map(|point| BoundingBox::new(point, point)));
+
+        bounds.fold(None, |bounds, next| Some(bounds.map_or(next, |bounds: BoundingBox| bounds.union(next))))
+    }
+
+    /// Returns every bezier of the start together with the one of the target
+    /// it turns into.
+    pub fn pairs<'a>(&'a self, groups: &GroupsRaw) -> Vec<BezierPair<'a>> {
+        let mid = self.beziers.len() / 2;
+
+        (0..mid).map(|index| {
+            let (start, target) = (&groups.0[index], &groups.0[mid + index]);
+
+            BezierPair {
+                start: &self.beziers[index],
+                target: &self.beziers[mid + index],
+                start_group: start.group_id,
+                target_group: target.group_id,
+                start_position: start.pos,
+                target_position: target.pos
+            }
+        }).collect()
+    }
+
+    pub fn draw<'a, R: Renderer>(&'a self, renderer: &mut R, groups: GroupsLinked<'a>, t: f64) {
         let color = self.start.color().lerp(self.target.color(), t);
 
         for group in groups.choose(t) {
-            group.draw(ctx, t, color);
+            group.draw(renderer, t, color);
+        }
+    }
+
+    /// Draws the morph at time `t`, with start and target as they are at both ends.
+    pub fn draw_frame<R: Renderer>(&self, renderer: &mut R, groups: &GroupsRaw, t: f64) {
+        if t == 0.0 {
+            self.start.draw(renderer);
+        } else if t == 1.0 {
+            self.target.draw(renderer);
+        } else {
+            self.draw(renderer, groups.link(self), t);
         }
     }
 }
 
+/// A bezier of the start and the one of the target it turns into, along with
+/// where both come from.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BezierPair<'a> {
+    start: &'a BezierCurve,
+    target: &'a BezierCurve,
+    start_group: usize,
+    target_group: usize,
+    start_position: usize,
+    target_position: usize
+}
+
+impl<'a> BezierPair<'a> {
+    pub fn start(&self) -> &'a BezierCurve {
+        self.start
+    }
+
+    pub fn target(&self) -> &'a BezierCurve {
+        self.target
+    }
+
+    /// Returns the index of the start group the bezier belongs to.
+    pub fn start_group(&self) -> usize {
+        self.start_group
+    }
+
+    /// Returns the index of the target group the bezier belongs to.
+    pub fn target_group(&self) -> usize {
+        self.target_group
+    }
+
+    /// Returns a number which orders the beziers of the start along its outline.
+    pub fn start_position(&self) -> usize {
+        self.start_position
+    }
+
+    /// Returns a number which orders the beziers of the target along its outline.
+    pub fn target_position(&self) -> usize {
+        self.target_position
+    }
+
+    /// Returns the bezier at time `t` of the morph.
+    pub fn at(&self, t: f64) -> BezierCurve {
+        self.start.lerp(self.target, t)
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 struct BezierGroup<'a> {
     segments: Vec<MorphSegment<'a>>,
@@ -168,37 +365,35 @@ impl<'a> BezierGroup<'a> {
         BezierGroup {segments, group}
     }
 
-    fn draw(&self, ctx: &Context, t: f64, color: (f64, f64, f64)) {
+    fn draw<R: Renderer>(&self, renderer: &mut R, t: f64, color: (f64, f64, f64)) {
         let u = if t < 0.5 {t} else {1.0 - t};
 
-        ctx.set_source_rgb(color.0, color.1, color.2);
+        renderer.set_color(color, 1.0);
         let mut segments = Vec::new();
 
         for segment in &self.segments {
             let line_width = self.group.line_width().lerp(&segment.group.line_width(), u);
             let segment = segment.start.lerp(&segment.target, t);
-            segment.draw(ctx, false);
-            ctx.set_line_width(line_width);
-            ctx.stroke();
+            segment.draw(renderer, true);
+            renderer.paint(Style::Stroke, line_width);
             segments.push(segment);
         }
 
         let t = (1.0 - 2.0 * t).abs();
 
-        ctx.set_source_rgba(color.0, color.1, color.2, t);
+        renderer.set_color(color, t);
         let mut begin = true;
 
         for segment in segments {
-            segment.draw(ctx, begin);
+            segment.draw(renderer, begin);
             begin = false;
         }
 
         if self.group.close() {
-            ctx.close_path();
+            renderer.close_path();
         }
 
-        ctx.set_line_width(t * self.group.line_width());
-        self.group.style().paint(ctx);
+        renderer.paint(self.group.style(), t * self.group.line_width());
     }
 }
 
@@ -216,3 +411,144 @@ impl<'a> MorphSegment<'a> {
         MorphSegment {start, target, group}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use serde_json;
+
+    use graphics::Graphic;
+    use super::{MorphGraphic, Subdivision};
+
+    fn empty() -> Rc<Graphic> {
+        Rc::new(serde_json::from_str(r#"{"color": [0.0, 0.0, 0.0], "groups": []}"#).unwrap())
+    }
+
+    fn square() -> Rc<Graphic> {
+        Rc::new(serde_json::from_str(r#"{
+            "color": [1.0, 0.0, 0.0],
+            "groups": [{
+                "segments": [
+                    {"type": "line", "start": "-0.5 | -0.5", "end": "0.5 | -0.5"},
+                    {"type": "line", "start": "0.5 | -0.5", "end": "0.5 | 0.5"},
+                    {"type": "line", "start": "0.5 | 0.5", "end": "-0.5 | 0.5"},
+                    {"type": "line", "start": "-0.5 | 0.5", "end": "-0.5 | -0.5"}
+                ],
+                "close": true
+            }]
+        }"#).unwrap())
+    }
+
+    fn lines(lines: &[(&str, &str)]) -> Rc<Graphic> {
+        let segments = lines.iter()
+            .map(|&(start, end)| format!(r#"{{"type": "line", "start": "{}", "end": "{}"}}"#, start, end))
+            .collect::<Vec<_>>();
+
+        let json = format!(r#"{{"color": [0.0, 0.0, 0.0], "groups": [{{"segments": [{}]}}]}}"#,
+            segments.join(", "));
+        Rc::new(serde_json::from_str(&json).unwrap())
+    }
+
+    fn is_collapsed(graphic: &Graphic) -> bool {
+        graphic.groups().all(|group| {
+            group.line_width() == 0.0 && group.segments().all(|segment| {
+                segment.start().length() < 1e-9
+                    && segment.end().length() < 1e-9
+            })
+        })
+    }
+
+    #[test]
+    fn morph_from_empty_grows_out_of_center() {
+        let target = square();
+        let (morph, groups) = MorphGraphic::new(empty(), target.clone());
+
+        assert_eq!(morph.target, target);
+        assert_eq!(morph.start.count_beziers(), target.count_beziers());
+        assert!(is_collapsed(&morph.start));
+        assert_eq!(morph.beziers.len(), 2 * target.count_beziers());
+
+        let linked = groups.link(&morph);
+        assert_eq!(linked.start.len(), 1);
+        assert_eq!(linked.target.len(), 1);
+    }
+
+    #[test]
+    fn morph_to_empty_shrinks_into_center() {
+        let start = square();
+        let (morph, groups) = MorphGraphic::new(start.clone(), empty());
+
+        assert_eq!(morph.start, start);
+        assert!(is_collapsed(&morph.target));
+        assert_eq!(morph.beziers.len(), 2 * start.count_beziers());
+
+        let linked = groups.link(&morph);
+        assert_eq!(linked.target[0].segments.len(), start.count_beziers());
+    }
+
+    #[test]
+    fn morph_between_empty_graphics_has_no_beziers() {
+        let (morph, groups) = MorphGraphic::new(empty(), empty());
+
+        assert!(morph.beziers.is_empty());
+
+        let linked = groups.link(&morph);
+        assert!(linked.choose(0.25).is_empty());
+        assert!(linked.choose(0.75).is_empty());
+    }
+
+    #[test]
+    fn proportional_subdivision_splits_by_length() {
+        let start = lines(&[("-0.75 | 0", "0.75 | 0"), ("0 | 0", "0 | 0.5")]);
+        let target = square();
+
+        assert_eq!(Subdivision::Proportional(0).splits(&start, 6), vec![4, 2]);
+        assert_eq!(Subdivision::Proportional(0).splits(&target, 6), vec![2, 2, 1, 1]);
+
+        let (morph, _) = MorphGraphic::with_subdivision(start.clone(), target.clone(),
+            Subdivision::Proportional(0));
+        assert_eq!(morph.beziers.len(), 2 * 4);
+
+        let (morph, _) = MorphGraphic::with_subdivision(start, target, Subdivision::Proportional(6));
+        assert_eq!(morph.beziers.len(), 2 * 6);
+    }
+
+    #[test]
+    fn least_common_multiple_subdivision_splits_evenly() {
+        let start = lines(&[("-0.75 | 0", "0.75 | 0"), ("0 | 0", "0 | 0.5")]);
+        let target = square();
+
+        assert_eq!(Subdivision::LeastCommonMultiple.splits(&start, 4), vec![2, 2]);
+        assert_eq!(Subdivision::LeastCommonMultiple.splits(&target, 4), vec![1, 1, 1, 1]);
+        assert_eq!(Subdivision::LeastCommonMultiple.splits(&target, 8), vec![2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn proportional_subdivision_ignores_invalid_lengths() {
+        // the length of the first line overflows to infinity
+        let huge = format!("1{}", "0".repeat(308));
+        let start = lines(&[(&format!("-{} | 0", huge), &format!("{} | 0", huge)), ("0 | 0", "0 | 1")]);
+        let splits = Subdivision::Proportional(0).splits(&start, 5);
+
+        assert_eq!(splits.iter().sum::<usize>(), 5);
+    }
+
+    #[test]
+    fn pairs_cover_both_graphics() {
+        let start = lines(&[("-0.75 | 0", "0.75 | 0"), ("0 | 0", "0 | 0.5")]);
+        let (morph, groups) = MorphGraphic::new(start, square());
+        let pairs = morph.pairs(&groups);
+
+        assert_eq!(pairs.len(), 4);
+        assert!(pairs.iter().all(|pair| pair.start_group() == 0 && pair.target_group() == 0));
+
+        let mut positions = pairs.iter().map(|pair| pair.target_position()).collect::<Vec<_>>();
+        positions.sort();
+        assert_eq!(positions, vec![1, 2, 3, 4]);
+
+        for pair in pairs {
+            assert_eq!(&pair.at(0.0), pair.start());
+            assert!(pair.at(1.0).end().distance(pair.target().end()) < 1e-12);
+        }
+    }
+}
@@ -1,12 +1,40 @@
+use std::fmt;
+use std::cmp;
 use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, Div};
 use std::f64::consts;
-use cairo::Context;
-
+use render::Renderer;
 use utils::{self, Lerp};
 
 const TAU: f64 = 2.0 * consts::PI;
+const EPSILON: f64 = 1e-12;
+// tolerance for matrix entries computed from angles, like the cosine of a right angle
+const ANGLE_EPSILON: f64 = 1e-9;
+
+// nodes and weights of the five point Gauss-Legendre quadrature
+const GAUSS_LEGENDRE: [(f64, f64); 5] = [
+    (0.0, 0.5688888888888889),
+    (-0.5384693101056831, 0.4786286704993665),
+    (0.5384693101056831, 0.4786286704993665),
+    (-0.906179845938664, 0.2369268850561891),
+    (0.906179845938664, 0.2369268850561891)
+];
+
+/// Integrates `f` from `start` to `end`, by applying the Gauss-Legendre
+/// quadrature on a few subintervals.
+pub fn integrate<F: Fn(f64) -> f64>(f: F, start: f64, end: f64) -> f64 {
+    const INTERVALS: usize = 8;
+    let width = (end - start) / INTERVALS as f64;
+
+    (0..INTERVALS).map(|i| {
+        let middle = start + (i as f64 + 0.5) * width;
+
+        GAUSS_LEGENDRE.iter()
+            .map(|&(node, weight)| weight * f(middle + node * width / 2.0))
+            .sum::<f64>() * width / 2.0
+    }).sum()
+}
 
-#[derive(Default, Clone, Copy, PartialEq, Debug, Serialize)]
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
 pub struct Point {
     x: f64,
     y: f64
@@ -17,6 +45,56 @@ impl Point {
         Point {x, y}
     }
 
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn distance(&self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+
+    pub fn dot(&self, other: Point) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Returns the z component of the cross product, which is positive if
+    /// `other` points counterclockwise of `self`.
+    pub fn cross(&self, other: Point) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Returns the distance to the origin.
+    pub fn length(&self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    /// Returns the point scaled to a length of one, or the origin if it is there already.
+    pub fn normalize(&self) -> Point {
+        match self.length() {
+            0.0 => *self,
+            length => *self / length
+        }
+    }
+
+    /// Rotates the point counterclockwise around the origin.
+    pub fn rotate(&self, angle: f64) -> Point {
+        let (sin, cos) = angle.sin_cos();
+
+        Point {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos
+        }
+    }
+
+    /// Returns the angle to the x axis, between -pi and pi.
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
     pub fn on_circle(&self, radius: f64, angle: f64) -> Point {
         Point {
             x: self.x + radius * angle.cos(),
@@ -32,6 +110,13 @@ impl Point {
     }
 }
 
+/// Formats the point like in the files, as `x | y`.
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} | {}", self.x, self.y)
+    }
+}
+
 impl From<(f64, f64)> for Point {
     fn from((x, y): (f64, f64)) -> Point {
         Point::new(x, y)
@@ -116,6 +201,209 @@ impl Lerp for Point {
     }
 }
 
+/// An axis aligned rectangle, given by its lower left and upper right corner.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BoundingBox {
+    min: Point,
+    max: Point
+}
+
+impl BoundingBox {
+    /// Creates the box spanned by two opposite corners.
+    pub fn new<P: Into<Point>>(corner: P, opposite: P) -> BoundingBox {
+        let (corner, opposite) = (corner.into(), opposite.into());
+
+        BoundingBox {
+            min: Point::new(corner.x.min(opposite.x), corner.y.min(opposite.y)),
+            max: Point::new(corner.x.max(opposite.x), corner.y.max(opposite.y))
+        }
+    }
+
+    /// Returns the square from -1 | -1 to 1 | 1, in which graphics are shown by default.
+    pub fn viewport() -> BoundingBox {
+        BoundingBox::new((-1.0, -1.0), (1.0, 1.0))
+    }
+
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn center(&self) -> Point {
+        (self.min + self.max) / 2.0
+    }
+
+    /// Grows the box to contain `point`.
+    pub fn include(self, point: Point) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(self.min.x.min(point.x), self.min.y.min(point.y)),
+            Point::new(self.max.x.max(point.x), self.max.y.max(point.y)))
+    }
+
+    pub fn union(self, other: BoundingBox) -> BoundingBox {
+        self.include(other.min).include(other.max)
+    }
+
+    /// Grows the box by `margin` on every side.
+    pub fn padded(self, margin: f64) -> BoundingBox {
+        let margin = Point::new(margin, margin);
+        BoundingBox::new(self.min - margin, self.max + margin)
+    }
+
+    /// Returns the box around the transformed corners.
+    pub fn transform(&self, affine: &Affine) -> BoundingBox {
+        let corners = [Point::new(self.min.x, self.max.y), Point::new(self.max.x, self.min.y), self.max];
+
+        corners.iter()
+            .map(|&corner| affine.apply(corner))
+            .fold(BoundingBox::new(affine.apply(self.min), affine.apply(self.min)), BoundingBox::include)
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.min.x <= point.x && point.x <= self.max.x && self.min.y <= point.y && point.y <= self.max.y
+    }
+
+    /// Checks whether the boxes share at least one point.
+    pub fn overlaps(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x
+            && self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+}
+
+/// A two dimensional affine transform, which maps x | y onto
+/// `xx * x + xy * y + x0 | yx * x + yy * y + y0`, like cairo's matrices.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Affine {
+    xx: f64,
+    yx: f64,
+    xy: f64,
+    yy: f64,
+    x0: f64,
+    y0: f64
+}
+
+impl Affine {
+    pub fn new(xx: f64, yx: f64, xy: f64, yy: f64, x0: f64, y0: f64) -> Affine {
+        Affine {xx, yx, xy, yy, x0, y0}
+    }
+
+    pub fn identity() -> Affine {
+        Affine::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    pub fn translate<P: Into<Point>>(offset: P) -> Affine {
+        let offset = offset.into();
+        Affine::new(1.0, 0.0, 0.0, 1.0, offset.x, offset.y)
+    }
+
+    pub fn scale(x: f64, y: f64) -> Affine {
+        Affine::new(x, 0.0, 0.0, y, 0.0, 0.0)
+    }
+
+    /// Rotates counterclockwise around the origin.
+    pub fn rotate(angle: f64) -> Affine {
+        let (sin, cos) = angle.sin_cos();
+        Affine::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// Returns the transform which applies `self` first and `other` afterwards.
+    pub fn then(&self, other: &Affine) -> Affine {
+        let origin = other.apply(Point::new(self.x0, self.y0));
+
+        Affine {
+            xx: other.xx * self.xx + other.xy * self.yx,
+            yx: other.yx * self.xx + other.yy * self.yx,
+            xy: other.xx * self.xy + other.xy * self.yy,
+            yy: other.yx * self.xy + other.yy * self.yy,
+            x0: origin.x,
+            y0: origin.y
+        }
+    }
+
+    /// Returns the entries in the order they are passed to `new`.
+    pub fn entries(&self) -> (f64, f64, f64, f64, f64, f64) {
+        (self.xx, self.yx, self.xy, self.yy, self.x0, self.y0)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.xx * self.yy - self.xy * self.yx
+    }
+
+    /// Returns the transform undoing this one, if it doesn't collapse the plane.
+    pub fn invert(&self) -> Option<Affine> {
+        let determinant = self.determinant();
+
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+
+        let (xx, yx) = (self.yy / determinant, -self.yx / determinant);
+        let (xy, yy) = (-self.xy / determinant, self.xx / determinant);
+
+        Some(Affine {
+            xx, yx, xy, yy,
+            x0: -(xx * self.x0 + xy * self.y0),
+            y0: -(yx * self.x0 + yy * self.y0)
+        })
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        self.apply_vector(point) + Point::new(self.x0, self.y0)
+    }
+
+    /// Applies the transform without the translation, as needed for directions.
+    pub fn apply_vector(&self, vector: Point) -> Point {
+        Point {
+            x: self.xx * vector.x + self.xy * vector.y,
+            y: self.yx * vector.x + self.yy * vector.y
+        }
+    }
+
+    /// Returns the factor by which lengths are scaled on average.
+    pub fn scale_factor(&self) -> f64 {
+        self.determinant().abs().sqrt()
+    }
+
+    /// Checks whether circles stay circles, as with rotations, mirroring and uniform scaling.
+    fn keeps_circles(&self) -> bool {
+        let rotation = (self.xx - self.yy).abs() < ANGLE_EPSILON && (self.xy + self.yx).abs() < ANGLE_EPSILON;
+        let reflection = (self.xx + self.yy).abs() < ANGLE_EPSILON && (self.xy - self.yx).abs() < ANGLE_EPSILON;
+
+        rotation || reflection
+    }
+
+    /// Returns the linear part with every entry reduced to its sign.
+    fn axis_signs(&self) -> Affine {
+        let sign = |value: f64| if value.abs() < ANGLE_EPSILON { 0.0 } else { value.signum() };
+        Affine::new(sign(self.xx), sign(self.yx), sign(self.xy), sign(self.yy), 0.0, 0.0)
+    }
+
+    /// Checks whether the axes are only scaled, mirrored or swapped.
+    fn keeps_axes(&self) -> bool {
+        let diagonal = self.xy.abs() < ANGLE_EPSILON && self.yx.abs() < ANGLE_EPSILON;
+        let swapped = self.xx.abs() < ANGLE_EPSILON && self.yy.abs() < ANGLE_EPSILON;
+
+        diagonal || swapped
+    }
+}
+
+impl Default for Affine {
+    fn default() -> Affine {
+        Affine::identity()
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum Segment {
@@ -126,15 +414,83 @@ pub enum Segment {
 }
 
 impl Segment {
-    pub fn draw(&self, ctx: &Context, begin: bool) {
+    pub fn draw<R: Renderer>(&self, renderer: &mut R, begin: bool) {
+        match *self {
+            Segment::Line(ref line) => line.draw(renderer, begin),
+            Segment::Arc(ref arc) => arc.draw(renderer),
+            Segment::OvalArc(ref arc) => arc.draw(renderer),
+            Segment::BezierCurve(ref bezier) => bezier.draw(renderer, begin)
+        }
+    }
+
+    pub fn start(&self) -> Point {
+        match *self {
+            Segment::Line(ref line) => line.start,
+            Segment::Arc(ref arc) => arc.center.on_circle(arc.radius, arc.start),
+            Segment::OvalArc(ref arc) => arc.center.on_oval(arc.radiusx, arc.radiusy, arc.start),
+            Segment::BezierCurve(ref bezier) => bezier.start
+        }
+    }
+
+    pub fn end(&self) -> Point {
+        match *self {
+            Segment::Line(ref line) => line.end,
+            Segment::Arc(ref arc) => arc.center.on_circle(arc.radius, arc.end),
+            Segment::OvalArc(ref arc) => arc.center.on_oval(arc.radiusx, arc.radiusy, arc.end),
+            Segment::BezierCurve(ref bezier) => bezier.end
+        }
+    }
+
+    /// Shrinks the segment into a single point, keeping its type and number of beziers.
+    pub fn collapse(&self, point: Point) -> Segment {
+        match *self {
+            Segment::Line(_) => Line::new(point, point).into(),
+            Segment::Arc(ref arc) => Arc::new(point, 0.0, arc.start, arc.end).into(),
+            Segment::OvalArc(ref arc) => OvalArc::new(point, 0.0, 0.0, arc.start, arc.end).into(),
+            Segment::BezierCurve(_) => BezierCurve::new(point, point, point, point).into()
+        }
+    }
+
+    /// Applies the transform to the segment. Arcs which would be distorted
+    /// into a shape they can't describe are replaced by their beziers.
+    pub fn transform(&self, affine: &Affine) -> Vec<Segment> {
+        let transformed = match *self {
+            Segment::Line(ref line) => Some(line.transform(affine).into()),
+            Segment::Arc(ref arc) => arc.transform(affine).map(Segment::from).or_else(|| {
+                // scaling a circle along the axes still gives an oval
+                OvalArc::new(arc.center, arc.radius, arc.radius, arc.start, arc.end)
+                    .transform(affine).map(Segment::from)
+            }),
+            Segment::OvalArc(ref arc) => arc.transform(affine).map(Segment::from),
+            Segment::BezierCurve(ref bezier) => Some(bezier.transform(affine).into())
+        };
+
+        // an arc without sweep counts no beziers, but must not vanish
+        match transformed {
+            Some(segment) => vec![segment],
+            None => self.to_beziers(cmp::max(self.count_beziers(), 1)).into_iter()
+                .map(|bezier| bezier.transform(affine).into())
+                .collect()
+        }
+    }
+
+    /// Returns the arc length of the segment.
+    pub fn length(&self) -> f64 {
         match *self {
-            Segment::Line(ref line) => line.draw(ctx, begin),
-            Segment::Arc(ref arc) => arc.draw(ctx),
-            Segment::OvalArc(ref arc) => arc.draw(ctx),
-            Segment::BezierCurve(ref bezier) => bezier.draw(ctx, begin)
+            Segment::Line(ref line) => line.start.distance(line.end),
+            Segment::Arc(ref arc) => (arc.radius * (arc.end - arc.start)).abs(),
+            Segment::OvalArc(ref arc) => arc.length(),
+            Segment::BezierCurve(ref bezier) => bezier.length()
         }
     }
 
+    /// Returns `count + 1` points along the segment, including both ends.
+    pub fn samples(&self, count: usize) -> Vec<Point> {
+        let mut points = vec![self.start()];
+        points.extend(self.to_beziers(count).into_iter().map(|bezier| bezier.end));
+        points
+    }
+
     pub fn count_beziers(&self) -> usize {
         match *self {
             Segment::Arc(ref arc) => {
@@ -184,11 +540,22 @@ impl Segment {
                 }).collect()
             },
             Segment::BezierCurve(ref bezier) => {
-                (0..count).scan(bezier.clone(), |bezier, i| {
-                    let t = 1.0 / (count - i) as f64;
-                    let hull = bezier.hull(t);
-                    *bezier = BezierCurve::new(hull[0], hull[4], hull[7], hull[9]);
-                    Some(bezier.clone())
+                let length = bezier.length() / count as f64;
+
+                // cut the pieces off the front of the remaining curve, each
+                // one at an equal share of the arc length
+                (0..count).scan((bezier.clone(), 0.0), |state, i| {
+                    let (ref mut rest, ref mut rest_start) = *state;
+
+                    if i + 1 == count {
+                        return Some(rest.clone());
+                    }
+
+                    let t = bezier.parameter_at_length((i + 1) as f64 * length);
+                    let (piece, remaining) = rest.split((t - *rest_start) / (1.0 - *rest_start));
+                    *rest = remaining;
+                    *rest_start = t;
+                    Some(piece)
                 }).collect()
             }
         }
@@ -233,14 +600,26 @@ impl Line {
         }
     }
 
-    fn draw(&self, ctx: &Context, begin: bool) {
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    pub fn end(&self) -> Point {
+        self.end
+    }
+
+    pub fn transform(&self, affine: &Affine) -> Line {
+        Line::new(affine.apply(self.start), affine.apply(self.end))
+    }
+
+    fn draw<R: Renderer>(&self, renderer: &mut R, begin: bool) {
         if begin {
-            ctx.move_to(self.start.x, self.start.y);
+            renderer.move_to(self.start);
         } else {
-            ctx.line_to(self.start.x, self.start.y);
+            renderer.line_to(self.start);
         }
 
-        ctx.line_to(self.end.x, self.end.y);
+        renderer.line_to(self.end);
     }
 }
 
@@ -256,18 +635,45 @@ pub struct Arc {
 }
 
 impl Arc {
-    pub fn new(center: Point, radius: f64, start: f64, end: f64) -> Arc {
-        Arc {center, radius, start, end}
+    pub fn new<P: Into<Point>>(center: P, radius: f64, start: f64, end: f64) -> Arc {
+        Arc {center: center.into(), radius, start, end}
     }
 
-    fn draw(&self, ctx: &Context) {
-        if self.start < self.end {
-            ctx.arc(self.center.x, self.center.y,
-                self.radius, self.start, self.end);
-        } else {
-            ctx.arc_negative(self.center.x, self.center.y,
-                self.radius, self.start, self.end);
+    pub fn center(&self) -> Point {
+        self.center
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn start_angle(&self) -> f64 {
+        self.start
+    }
+
+    pub fn end_angle(&self) -> f64 {
+        self.end
+    }
+
+    pub fn sweep(&self) -> f64 {
+        self.end - self.start
+    }
+
+    /// Applies the transform, if it keeps circles round.
+    pub fn transform(&self, affine: &Affine) -> Option<Arc> {
+        if !affine.keeps_circles() {
+            return None;
         }
+
+        let start = affine.apply_vector(Point::new(1.0, 0.0).rotate(self.start)).angle();
+        // mirroring reverses the direction
+        let sweep = affine.determinant().signum() * self.sweep();
+
+        Some(Arc::new(affine.apply(self.center), self.radius * affine.scale_factor(), start, start + sweep))
+    }
+
+    fn draw<R: Renderer>(&self, renderer: &mut R) {
+        renderer.arc(self.center, self.radius, self.radius, self.start, self.end);
     }
 }
 
@@ -295,16 +701,75 @@ pub struct OvalArc {
 }
 
 impl OvalArc {
-    fn new(center: Point, radiusx: f64, radiusy: f64, start: f64, end: f64) -> OvalArc {
-        OvalArc {center, radiusx, radiusy, start, end}
+    pub fn new<P: Into<Point>>(center: P, radiusx: f64, radiusy: f64, start: f64, end: f64) -> OvalArc {
+        OvalArc {center: center.into(), radiusx, radiusy, start, end}
+    }
+
+    pub fn center(&self) -> Point {
+        self.center
+    }
+
+    pub fn radiusx(&self) -> f64 {
+        self.radiusx
+    }
+
+    pub fn radiusy(&self) -> f64 {
+        self.radiusy
+    }
+
+    pub fn start_angle(&self) -> f64 {
+        self.start
+    }
+
+    pub fn end_angle(&self) -> f64 {
+        self.end
+    }
+
+    fn length(&self) -> f64 {
+        let speed = |angle: f64| {
+            (self.radiusx * angle.sin()).hypot(self.radiusy * angle.cos())
+        };
+
+        integrate(speed, self.start, self.end).abs()
     }
 
-    fn draw(&self, ctx: &Context) {
-        ctx.save();
-        ctx.translate(self.center.x, self.center.y);
-        ctx.scale(self.radiusx, self.radiusy);
-        ctx.arc(0.0, 0.0, 1.0, self.start, self.end);
-        ctx.restore();
+    pub fn sweep(&self) -> f64 {
+        self.end - self.start
+    }
+
+    /// Applies the transform, if it keeps the axes of the oval aligned.
+    pub fn transform(&self, affine: &Affine) -> Option<OvalArc> {
+        if !affine.keeps_axes() {
+            return None;
+        }
+
+        // the radii follow the axes they are mapped onto
+        let x = affine.apply_vector(Point::new(self.radiusx, 0.0));
+        let y = affine.apply_vector(Point::new(0.0, self.radiusy));
+        let (radiusx, radiusy) = if affine.xy.abs() < ANGLE_EPSILON {
+            (x.x.abs(), y.y.abs())
+        } else {
+            (y.x.abs(), x.y.abs())
+        };
+
+        // the angle parametrizes a circle, which is only mirrored or turned by right angles
+        let (sin, cos) = self.start.sin_cos();
+        let start = affine.axis_signs().apply_vector(Point::new(cos, sin)).angle();
+        let sweep = affine.determinant().signum() * self.sweep();
+
+        Some(OvalArc::new(affine.apply(self.center), radiusx, radiusy, start, start + sweep))
+    }
+
+    fn draw<R: Renderer>(&self, renderer: &mut R) {
+        // oval arcs are always drawn in positive direction, so the end is
+        // raised by whole turns until it lies past the start
+        let end = if self.end < self.start {
+            self.end + ((self.start - self.end) / TAU).ceil() * TAU
+        } else {
+            self.end
+        };
+
+        renderer.arc(self.center, self.radiusx, self.radiusy, self.start, end);
     }
 }
 
@@ -329,8 +794,29 @@ pub struct BezierCurve {
 }
 
 impl BezierCurve {
-    fn new(start: Point, control1: Point, control2: Point, end: Point) -> BezierCurve {
-        BezierCurve {start, control1, control2, end}
+    pub fn new<P: Into<Point>>(start: P, control1: P, control2: P, end: P) -> BezierCurve {
+        BezierCurve {
+            start: start.into(),
+            control1: control1.into(),
+            control2: control2.into(),
+            end: end.into()
+        }
+    }
+
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    pub fn control1(&self) -> Point {
+        self.control1
+    }
+
+    pub fn control2(&self) -> Point {
+        self.control2
+    }
+
+    pub fn end(&self) -> Point {
+        self.end
     }
 
     fn hull(&self, state: f64) -> Vec<Point> {
@@ -350,15 +836,192 @@ impl BezierCurve {
         hull
     }
 
-    pub fn draw(&self, ctx: &Context, begin: bool) {
+    pub fn transform(&self, affine: &Affine) -> BezierCurve {
+        BezierCurve::new(affine.apply(self.start), affine.apply(self.control1),
+            affine.apply(self.control2), affine.apply(self.end))
+    }
+
+    /// Returns the point on the curve at the parameter `t`.
+    pub fn point_at(&self, t: f64) -> Point {
+        let u = 1.0 - t;
+
+        u * u * u * self.start
+            + 3.0 * u * u * t * self.control1
+            + 3.0 * u * t * t * self.control2
+            + t * t * t * self.end
+    }
+
+    /// Splits the curve at `t` into the part before and the part after it.
+    pub fn split(&self, t: f64) -> (BezierCurve, BezierCurve) {
+        let hull = self.hull(t);
+
+        (BezierCurve::new(hull[0], hull[4], hull[7], hull[9]),
+            BezierCurve::new(hull[9], hull[8], hull[6], hull[3]))
+    }
+
+    pub fn derivative(&self, t: f64) -> Point {
+        let u = 1.0 - t;
+
+        3.0 * u * u * (self.control1 - self.start)
+            + 6.0 * u * t * (self.control2 - self.control1)
+            + 3.0 * t * t * (self.end - self.control2)
+    }
+
+    pub fn second_derivative(&self, t: f64) -> Point {
+        let u = 1.0 - t;
+
+        6.0 * u * (self.control2 - 2.0 * self.control1 + self.start)
+            + 6.0 * t * (self.end - 2.0 * self.control2 + self.control1)
+    }
+
+    /// Returns the direction of the curve at `t` with a length of one.
+    ///
+    /// Where a control point coincides with its end point, the derivative
+    /// vanishes and the direction is taken from the second derivative instead.
+    pub fn tangent(&self, t: f64) -> Point {
+        let mut direction = self.derivative(t);
+
+        if direction.length() < EPSILON {
+            direction = self.second_derivative(t);
+        }
+
+        if direction.length() < EPSILON {
+            direction = self.end - self.start;
+        }
+
+        if direction.length() < EPSILON {
+            Point::default()
+        } else {
+            direction.normalize()
+        }
+    }
+
+    /// Returns the tangent at `t`, turned counterclockwise by a right angle.
+    pub fn normal(&self, t: f64) -> Point {
+        let tangent = self.tangent(t);
+        Point::new(-tangent.y, tangent.x)
+    }
+
+    /// Returns the smallest axis aligned box containing the curve, which is
+    /// usually smaller than the one around the control points.
+    pub fn bounding_box(&self) -> BoundingBox {
+        let a = self.control1 - self.start;
+        let b = self.control2 - self.control1;
+        let c = self.end - self.control2;
+
+        // the extrema lie where the quadratic derivative of either axis is zero
+        let mut parameters = quadratic_roots(a.x - 2.0 * b.x + c.x, 2.0 * (b.x - a.x), a.x);
+        parameters.extend(quadratic_roots(a.y - 2.0 * b.y + c.y, 2.0 * (b.y - a.y), a.y));
+
+        parameters.into_iter()
+            .filter(|&t| t > 0.0 && t < 1.0)
+            .map(|t| self.point_at(t))
+            .fold(BoundingBox::new(self.start, self.end), BoundingBox::include)
+    }
+
+    /// Returns the arc length from the start up to the parameter `t`.
+    pub fn length_until(&self, t: f64) -> f64 {
+        integrate(|t| self.derivative(t).length(), 0.0, t)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length_until(1.0)
+    }
+
+    /// Finds the parameter at which the arc length from the start reaches `length`.
+    fn parameter_at_length(&self, length: f64) -> f64 {
+        let (mut low, mut high) = (0.0, 1.0);
+
+        // the arc length grows monotonically with the parameter
+        for _ in 0..40 {
+            let middle = (low + high) / 2.0;
+
+            if self.length_until(middle) < length {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        (low + high) / 2.0
+    }
+
+    /// Returns the parameter of the point on the curve closest to `point`.
+    pub fn nearest(&self, point: Point) -> f64 {
+        const SAMPLES: usize = 64;
+        let distance = |t: f64| self.point_at(t).distance(point);
+
+        // find the closest sample, then narrow down its surroundings
+        let closest = (0..SAMPLES + 1)
+            .map(|i| i as f64 / SAMPLES as f64)
+            .fold(0.0, |best, t| if distance(t) < distance(best) { t } else { best });
+
+        let step = 1.0 / SAMPLES as f64;
+        let (mut low, mut high) = (f64::max(closest - step, 0.0), f64::min(closest + step, 1.0));
+
+        for _ in 0..60 {
+            let third = (high - low) / 3.0;
+
+            if distance(low + third) < distance(high - third) {
+                high -= third;
+            } else {
+                low += third;
+            }
+        }
+
+        (low + high) / 2.0
+    }
+
+    /// Returns the parameters on both curves where they cross each other.
+    ///
+    /// Both curves are subdivided until their pieces are flat enough to be
+    /// intersected as lines, so overlapping curves have no intersections.
+    pub fn intersections(&self, other: &BezierCurve) -> Vec<(f64, f64)> {
+        let bounds = self.bounding_box().union(other.bounding_box());
+        // flat enough relative to the size of the curves, so large coordinates need no deeper splits
+        let tolerance = FLATNESS * f64::max(bounds.width(), bounds.height());
+
+        let mut intersections = Vec::new();
+        intersect(self, (0.0, 1.0), other, (0.0, 1.0), tolerance, 0, &mut intersections);
+        intersections
+    }
+
+    /// Returns whether both curves run along the same path, in either direction.
+    fn coincides(&self, other: &BezierCurve, tolerance: f64) -> bool {
+        let close = |a: Point, b: Point| (a - b).length() <= tolerance;
+
+        (close(self.start, other.start) && close(self.control1, other.control1)
+            && close(self.control2, other.control2) && close(self.end, other.end))
+            || (close(self.start, other.end) && close(self.control1, other.control2)
+            && close(self.control2, other.control1) && close(self.end, other.start))
+    }
+
+    /// Returns the largest distance of the control points to the line between the ends.
+    fn flatness(&self) -> f64 {
+        let chord = self.end - self.start;
+        let length = chord.length();
+
+        let distance = |point: Point| {
+            let offset = point - self.start;
+
+            if length < EPSILON {
+                offset.length()
+            } else {
+                chord.cross(offset).abs() / length
+            }
+        };
+
+        f64::max(distance(self.control1), distance(self.control2))
+    }
+
+    pub fn draw<R: Renderer>(&self, renderer: &mut R, begin: bool) {
         if begin {
-            ctx.move_to(self.start.x, self.start.y);
+            renderer.move_to(self.start);
         } else {
-            ctx.line_to(self.start.x, self.start.y);
+            renderer.line_to(self.start);
         }
 
-        ctx.curve_to(self.control1.x, self.control1.y,
-            self.control2.x, self.control2.y, self.end.x, self.end.y);
+        renderer.curve_to(self.control1, self.control2, self.end);
     }
 }
 
@@ -410,3 +1073,401 @@ impl From<OvalArc> for BezierCurve {
             end.on_oval(dx, dy, value.end + consts::FRAC_PI_2), end)
     }
 }
+
+/// Returns the real roots of `a t^2 + b t + c`, which is treated as linear if `a` vanishes.
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < EPSILON {
+        return if b.abs() < EPSILON { Vec::new() } else { vec![-c / b] };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+
+    let root = discriminant.sqrt();
+    vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+}
+
+/// Largest distance of the control points from the chord, relative to the
+/// size of the curves, at which pieces are intersected as lines
+const FLATNESS: f64 = 1e-9;
+/// Deepest subdivision, which keeps curves running along each other from
+/// being split forever
+const MAX_DEPTH: usize = 32;
+
+/// How far flat pieces may stray from each other's lines, as a multiple of the
+/// flatness, to still be taken as running along each other
+const COLLINEAR: f64 = 16.0;
+
+/// Returns whether the ends of both curves lie within `distance` of the line through the other.
+fn collinear(first: &BezierCurve, second: &BezierCurve, distance: f64) -> bool {
+    let near = |curve: &BezierCurve, point: Point| {
+        let chord = curve.end - curve.start;
+        let length = chord.length();
+
+        length >= EPSILON && chord.cross(point - curve.start).abs() / length <= distance
+    };
+
+    near(first, second.start) && near(first, second.end)
+        && near(second, first.start) && near(second, first.end)
+}
+
+fn intersect(first: &BezierCurve, first_range: (f64, f64), second: &BezierCurve,
+        second_range: (f64, f64), tolerance: f64, depth: usize, intersections: &mut Vec<(f64, f64)>) {
+    if !first.bounding_box().overlaps(&second.bounding_box()) {
+        return;
+    }
+
+    // overlapping pieces touch everywhere, rather than crossing somewhere
+    if first.coincides(second, tolerance) {
+        return;
+    }
+
+    let flat = first.flatness() <= tolerance && second.flatness() <= tolerance;
+
+    if flat || depth >= MAX_DEPTH {
+        // flat pieces of curves running along each other lie on one line
+        if collinear(first, second, COLLINEAR * tolerance) {
+            return;
+        }
+
+        if let Some((s, u)) = intersect_lines(first.start, first.end, second.start, second.end) {
+            let t = first_range.0 + s * (first_range.1 - first_range.0);
+            let v = second_range.0 + u * (second_range.1 - second_range.0);
+
+            // pieces meeting at their ends find the same intersection twice
+            let known = intersections.iter()
+                .any(|&(known_t, known_v)| (known_t - t).abs() < 1e-6 && (known_v - v).abs() < 1e-6);
+
+            if !known {
+                intersections.push((t, v));
+            }
+        }
+
+        return;
+    }
+
+    let (first_left, first_right) = first.split(0.5);
+    let (second_left, second_right) = second.split(0.5);
+    let first_middle = (first_range.0 + first_range.1) / 2.0;
+    let second_middle = (second_range.0 + second_range.1) / 2.0;
+
+    for &(first, first_range) in &[(&first_left, (first_range.0, first_middle)),
+            (&first_right, (first_middle, first_range.1))] {
+        for &(second, second_range) in &[(&second_left, (second_range.0, second_middle)),
+                (&second_right, (second_middle, second_range.1))] {
+            intersect(first, first_range, second, second_range, tolerance, depth + 1, intersections);
+        }
+    }
+}
+
+/// Returns where the lines from `a` to `b` and from `c` to `d` cross, as
+/// fractions along both of them.
+fn intersect_lines(a: Point, b: Point, c: Point, d: Point) -> Option<(f64, f64)> {
+    let (first, second, offset) = (b - a, d - c, c - a);
+    let denominator = first.cross(second);
+
+    if denominator.abs() < EPSILON * EPSILON {
+        return None;
+    }
+
+    let s = offset.cross(second) / denominator;
+    let u = offset.cross(first) / denominator;
+    // allow for rounding where the lines cross exactly at an end
+    let within = |fraction: f64| fraction > -1e-9 && fraction < 1.0 + 1e-9;
+
+    if within(s) && within(u) {
+        Some((s.clamp(0.0, 1.0), u.clamp(0.0, 1.0)))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts;
+
+    use super::{Point, BoundingBox, Affine, Segment, Line, Arc, OvalArc, BezierCurve};
+
+    const EPSILON: f64 = 1e-9;
+
+    fn curve() -> BezierCurve {
+        BezierCurve::new(Point::new(-1.0, 0.0), Point::new(-1.0, 1.0),
+            Point::new(0.5, 1.0), Point::new(1.0, -0.5))
+    }
+
+    /// A symmetric arch from 0 | 0 to 1 | 0, which is highest at 0.5 | 0.75.
+    fn arch() -> BezierCurve {
+        BezierCurve::new((0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0))
+    }
+
+    fn assert_close(actual: Point, expected: Point) {
+        assert!(actual.distance(expected) < 1e-6, "expected {:?}, got {:?}", expected, actual);
+    }
+
+    #[test]
+    fn length_of_lines_and_arcs() {
+        let line = Segment::from(Line::new((0.0, 0.0), (0.3, 0.4)));
+        assert!((line.length() - 0.5).abs() < EPSILON);
+
+        let arc = Segment::from(Arc::new(Point::default(), 0.5, consts::PI, 0.0));
+        assert!((arc.length() - consts::FRAC_PI_2).abs() < EPSILON);
+
+        let circle = Segment::from(OvalArc::new(Point::default(), 0.5, 0.5, 0.0, 2.0 * consts::PI));
+        assert!((circle.length() - consts::PI).abs() < EPSILON);
+    }
+
+    #[test]
+    fn length_of_straight_bezier() {
+        let bezier = Segment::from(BezierCurve::from(Line::new((0.0, 0.0), (0.3, 0.4))));
+        assert!((bezier.length() - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn bezier_splits_at_equal_length() {
+        let bezier = Segment::from(curve());
+        let pieces = bezier.to_beziers(4);
+
+        assert_eq!(pieces.len(), 4);
+        assert_eq!(pieces[0].start, bezier.start());
+        assert!(pieces[3].end.distance(bezier.end()) < EPSILON);
+
+        for pair in pieces.windows(2) {
+            assert!(pair[0].end.distance(pair[1].start) < EPSILON);
+        }
+
+        for piece in &pieces {
+            assert!((piece.length() - bezier.length() / 4.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn bezier_evaluates_points() {
+        let bezier = arch();
+
+        assert_close(bezier.point_at(0.0), Point::new(0.0, 0.0));
+        assert_close(bezier.point_at(0.5), Point::new(0.5, 0.75));
+        assert_close(bezier.point_at(1.0), Point::new(1.0, 0.0));
+
+        for &t in &[0.0, 0.3, 0.7, 1.0] {
+            assert_close(bezier.point_at(t), *bezier.hull(t).last().unwrap());
+        }
+    }
+
+    #[test]
+    fn bezier_derivatives_match_differences() {
+        let bezier = curve();
+        let h = 1e-5;
+
+        for &t in &[0.1, 0.5, 0.9] {
+            let difference = (bezier.point_at(t + h) - bezier.point_at(t - h)) / (2.0 * h);
+            assert!(difference.distance(bezier.derivative(t)) < 1e-4);
+
+            let difference = (bezier.derivative(t + h) - bezier.derivative(t - h)) / (2.0 * h);
+            assert!(difference.distance(bezier.second_derivative(t)) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn bezier_tangent_and_normal() {
+        let bezier = arch();
+
+        assert_close(bezier.tangent(0.0), Point::new(0.0, 1.0));
+        assert_close(bezier.tangent(0.5), Point::new(1.0, 0.0));
+        assert_close(bezier.normal(0.5), Point::new(0.0, 1.0));
+
+        // the first control point coincides with the start
+        let bezier = BezierCurve::new((0.0, 0.0), (0.0, 0.0), (1.0, 1.0), (2.0, 1.0));
+        assert_close(bezier.tangent(0.0), Point::new(1.0, 1.0) / 2f64.sqrt());
+
+        let bezier = curve();
+        for &t in &[0.2, 0.6] {
+            let (tangent, normal) = (bezier.tangent(t), bezier.normal(t));
+            assert!((tangent.length() - 1.0).abs() < EPSILON);
+            assert!(tangent.dot(normal).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn bezier_splits_at_parameter() {
+        let bezier = curve();
+        let (left, right) = bezier.split(0.3);
+
+        assert_close(left.end, bezier.point_at(0.3));
+        assert_close(right.start, bezier.point_at(0.3));
+        assert_close(left.point_at(0.5), bezier.point_at(0.15));
+        assert_close(right.point_at(0.5), bezier.point_at(0.65));
+    }
+
+    #[test]
+    fn bezier_bounding_box_is_tight() {
+        let bounds = arch().bounding_box();
+
+        assert_close(bounds.min(), Point::new(0.0, 0.0));
+        assert_close(bounds.max(), Point::new(1.0, 0.75));
+
+        let bezier = curve();
+        let bounds = bezier.bounding_box();
+        // allow for rounding at the extrema
+        let margin = Point::new(EPSILON, EPSILON);
+        let grown = BoundingBox::new(bounds.min() - margin, bounds.max() + margin);
+
+        for i in 0..101 {
+            assert!(grown.contains(bezier.point_at(i as f64 / 100.0)));
+        }
+
+        assert!(bounds.max().y < 1.0);
+    }
+
+    #[test]
+    fn bezier_length_of_arch() {
+        let bezier = arch();
+        // approximate the length with many short lines
+        let polyline: f64 = (0..1000)
+            .map(|i| bezier.point_at(i as f64 / 1000.0).distance(bezier.point_at((i + 1) as f64 / 1000.0)))
+            .sum();
+
+        assert!((bezier.length() - polyline).abs() < 1e-5);
+        assert!((bezier.length_until(0.5) - bezier.length() / 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn bezier_finds_nearest_point() {
+        let bezier = arch();
+
+        assert!((bezier.nearest(Point::new(0.5, 2.0)) - 0.5).abs() < 1e-6);
+        assert!(bezier.nearest(Point::new(-1.0, -1.0)) < 1e-6);
+
+        let t = bezier.nearest(Point::new(0.9, 0.9));
+        let offset = Point::new(0.9, 0.9) - bezier.point_at(t);
+        let tangent = bezier.tangent(t);
+        // the offset to the nearest point is perpendicular to the curve
+        assert!(offset.dot(tangent).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bezier_intersections() {
+        let bezier = arch();
+        let vertical = BezierCurve::from(Line::new((0.5, -1.0), (0.5, 2.0)));
+
+        let intersections = bezier.intersections(&vertical);
+        assert_eq!(intersections.len(), 1);
+        assert!((intersections[0].0 - 0.5).abs() < 1e-6);
+        assert!((intersections[0].1 - 1.75 / 3.0).abs() < 1e-6);
+
+        // a horizontal line crosses both flanks of the arch
+        let horizontal = BezierCurve::from(Line::new((-1.0, 0.5), (2.0, 0.5)));
+        let intersections = bezier.intersections(&horizontal);
+        assert_eq!(intersections.len(), 2);
+
+        for (t, u) in intersections {
+            assert_close(bezier.point_at(t), horizontal.point_at(u));
+        }
+
+        let apart = BezierCurve::from(Line::new((0.0, 1.0), (1.0, 1.0)));
+        assert!(bezier.intersections(&apart).is_empty());
+    }
+
+    #[test]
+    fn overlapping_bezier_intersections() {
+        let bezier = arch();
+        assert!(bezier.intersections(&bezier).is_empty());
+
+        let reversed = BezierCurve::new(bezier.end, bezier.control2, bezier.control1, bezier.start);
+        assert!(bezier.intersections(&reversed).is_empty());
+
+        // pieces running along each other may touch anywhere, but only on both curves
+        let (first, _) = bezier.split(0.75);
+        let (_, second) = bezier.split(0.25);
+
+        for (t, u) in first.intersections(&second) {
+            assert_close(first.point_at(t), second.point_at(u));
+        }
+    }
+
+    #[test]
+    fn large_bezier_intersections() {
+        let scale = Affine::scale(1e6, 1e6);
+        let bezier = arch().transform(&scale);
+        let vertical = BezierCurve::from(Line::new((0.5, -1.0), (0.5, 2.0))).transform(&scale);
+
+        let intersections = bezier.intersections(&vertical);
+        assert_eq!(intersections.len(), 1);
+        assert!((intersections[0].0 - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn point_vector_math() {
+        let point = Point::new(3.0, 4.0);
+
+        assert_eq!(point.dot(Point::new(2.0, -1.0)), 2.0);
+        assert_eq!(point.cross(Point::new(2.0, -1.0)), -11.0);
+        assert_eq!(point.length(), 5.0);
+        assert_close(point.normalize(), Point::new(0.6, 0.8));
+        assert_eq!(Point::default().normalize(), Point::default());
+        assert_close(point.rotate(consts::FRAC_PI_2), Point::new(-4.0, 3.0));
+        assert!((Point::new(-1.0, 1.0).angle() - 3.0 * consts::FRAC_PI_4).abs() < EPSILON);
+    }
+
+    #[test]
+    fn affine_composes_and_inverts() {
+        let affine = Affine::rotate(consts::FRAC_PI_2)
+            .then(&Affine::scale(2.0, 3.0))
+            .then(&Affine::translate((1.0, -1.0)));
+
+        // 1 | 2 turns to -2 | 1, is scaled to -4 | 3 and moved to -3 | 2
+        assert_close(affine.apply(Point::new(1.0, 2.0)), Point::new(-3.0, 2.0));
+        assert_close(affine.apply_vector(Point::new(1.0, 2.0)), Point::new(-4.0, 3.0));
+
+        let inverse = affine.invert().unwrap();
+        assert_close(inverse.apply(Point::new(-3.0, 2.0)), Point::new(1.0, 2.0));
+        assert_close(affine.then(&inverse).apply(Point::new(0.3, 0.7)), Point::new(0.3, 0.7));
+
+        assert_eq!(Affine::scale(0.0, 1.0).invert(), None);
+    }
+
+    #[test]
+    fn affine_transforms_segments() {
+        let segments = vec![
+            Segment::from(Line::new((0.0, 0.0), (0.3, 0.4))),
+            Segment::from(Arc::new((0.2, 0.1), 0.5, 0.25, 2.5)),
+            Segment::from(OvalArc::new((0.2, 0.1), 0.5, 0.3, -0.5, 4.0)),
+            Segment::from(curve()),
+            // without any sweep, which still takes one bezier
+            Segment::from(Arc::new((0.2, 0.1), 0.5, 1.0, 1.0)),
+            Segment::from(OvalArc::new((0.2, 0.1), 0.5, 0.3, 1.0, 1.0))
+        ];
+
+        let affines = vec![
+            Affine::rotate(0.7).then(&Affine::translate((0.5, -0.5))),
+            Affine::scale(-2.0, 2.0),
+            Affine::rotate(consts::FRAC_PI_2).then(&Affine::scale(1.0, 0.5)),
+            Affine::new(1.0, 0.0, 0.5, 1.0, 0.0, 0.0)
+        ];
+
+        for segment in &segments {
+            for affine in &affines {
+                let transformed = segment.transform(affine);
+
+                assert_close(transformed[0].start(), affine.apply(segment.start()));
+                assert_close(transformed.last().unwrap().end(), affine.apply(segment.end()));
+
+                // beziers are sampled by length, which the transform may distort
+                let sampled_evenly = match *segment {
+                    Segment::BezierCurve(_) => false,
+                    _ => transformed.len() == 1
+                };
+
+                if sampled_evenly {
+                    let expected = segment.samples(8).into_iter().map(|point| affine.apply(point));
+
+                    for (actual, expected) in transformed[0].samples(8).into_iter().zip(expected) {
+                        assert_close(actual, expected);
+                    }
+                }
+            }
+        }
+    }
+}
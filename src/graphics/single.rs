@@ -1,31 +1,59 @@
 use std::slice::Iter;
 use std::path::Path;
 use std::fs::File;
-use std::error;
+use std::io::Read;
 use serde_json;
-use cairo::Context;
-
+use render::Renderer;
 use utils;
-use super::Style;
-use super::segments::Segment;
+use utils::deserialize;
+use super::{Style, LoadError};
+use super::segments::{Point, BoundingBox, Affine, Segment, Line, Arc, OvalArc, BezierCurve};
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Graphic {
     color: (f64, f64, f64),
-    groups: Vec<Group>
+    groups: Vec<Group>,
+    /// The area the graphic is meant to be shown in, if it differs from the viewport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    viewbox: Option<BoundingBox>
 }
 
 impl Graphic {
-    pub fn load<P: AsRef<Path>>(path: P) -> Result<Graphic, Box<error::Error>> {
-        let file = File::open(path)?;
-        let graphic = serde_json::from_reader(file)?;
-        Ok(graphic)
+    /// Starts building a black graphic without any groups.
+    pub fn builder() -> GraphicBuilder {
+        GraphicBuilder::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Graphic, LoadError> {
+        let path = path.as_ref();
+        let mut source = String::new();
+
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut source))
+            .map_err(|err| LoadError::io(path, err))?;
+
+        Graphic::parse(path, &source)
+    }
+
+    /// Reads a graphic from JSON source, reporting errors as if it came from `path`.
+    pub fn parse<P: AsRef<Path>>(path: P, source: &str) -> Result<Graphic, LoadError> {
+        let path = path.as_ref();
+
+        // drop a grammar failure left over from deserializing something else
+        deserialize::take_grammar_failure();
+
+        serde_json::from_str(source)
+            .map_err(|err| LoadError::json(path, source, err))
     }
 
     pub fn color(&self) -> &(f64, f64, f64) {
         &self.color
     }
 
+    pub fn viewbox(&self) -> Option<BoundingBox> {
+        self.viewbox
+    }
+
     pub fn group(&self, index: usize) -> &Group {
         &self.groups[index]
     }
@@ -34,15 +62,58 @@ impl Graphic {
         self.groups.iter()
     }
 
+    /// Swaps the segment at `segment_id` of the group at `group_id` for another one.
+    pub fn set_segment(&mut self, group_id: usize, segment_id: usize, segment: Segment) {
+        self.groups[group_id].segments[segment_id] = segment;
+    }
+
     pub fn count_beziers(&self) -> usize {
         self.groups().map(Group::count_beziers).sum()
     }
 
-    pub fn draw(&self, ctx: &Context) {
-        ctx.set_source_rgb(self.color.0, self.color.1, self.color.2);
+    /// Returns a graphic of the same structure, but with every segment shrunk
+    /// into the center and no line width, so that other graphics can grow out of it.
+    pub fn collapse(&self) -> Graphic {
+        let samples = self.groups()
+            .flat_map(Group::segments)
+            .flat_map(|segment| segment.samples(4))
+            .collect::<Vec<_>>();
+
+        let center = match samples.len() {
+            0 => Point::default(),
+            count => samples.into_iter().fold(Point::default(), |sum, point| sum + point) / count as f64
+        };
+
+        let groups = self.groups.iter().map(|group| {
+            Group {
+                segments: group.segments().map(|segment| segment.collapse(center)).collect(),
+                line_width: 0.0,
+                .. group.clone()
+            }
+        }).collect();
+
+        Graphic {
+            color: self.color,
+            groups,
+            viewbox: self.viewbox
+        }
+    }
+
+    /// Returns the graphic with the transform applied to all segments, and
+    /// the line widths scaled along.
+    pub fn transform(&self, affine: &Affine) -> Graphic {
+        Graphic {
+            color: self.color,
+            groups: self.groups().map(|group| group.transform(affine)).collect(),
+            viewbox: self.viewbox.map(|viewbox| viewbox.transform(affine))
+        }
+    }
+
+    pub fn draw<R: Renderer>(&self, renderer: &mut R) {
+        renderer.set_color(self.color, 1.0);
 
         for group in &self.groups {
-            group.draw(ctx);
+            group.draw(renderer);
         }
     }
 }
@@ -58,6 +129,15 @@ pub struct Group {
 }
 
 impl Group {
+    /// Starts building an open group with the same defaults as in the file format.
+    pub fn builder() -> GroupBuilder {
+        GroupBuilder::default()
+    }
+
+    pub fn segment(&self, index: usize) -> &Segment {
+        &self.segments[index]
+    }
+
     pub fn segments(&self) -> Iter<Segment> {
         self.segments.iter()
     }
@@ -74,24 +154,31 @@ impl Group {
         self.close
     }
 
-    fn count_beziers(&self) -> usize {
+    pub fn count_beziers(&self) -> usize {
         self.segments.iter().map(Segment::count_beziers).sum()
     }
 
-    fn draw(&self, ctx: &Context) {
+    pub fn transform(&self, affine: &Affine) -> Group {
+        Group {
+            segments: self.segments().flat_map(|segment| segment.transform(affine)).collect(),
+            line_width: self.line_width * affine.scale_factor(),
+            .. self.clone()
+        }
+    }
+
+    fn draw<R: Renderer>(&self, renderer: &mut R) {
         let mut begin = true;
 
         for segment in &self.segments {
-            segment.draw(ctx, begin);
+            segment.draw(renderer, begin);
             begin = false;
         }
 
         if self.close {
-            ctx.close_path();
+            renderer.close_path();
         }
 
-        ctx.set_line_width(self.line_width);
-        self.style.paint(ctx);
+        renderer.paint(self.style, self.line_width);
     }
 }
 
@@ -114,3 +201,150 @@ impl<T: Into<Segment>> From<T> for Group {
         }
     }
 }
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GraphicBuilder {
+    graphic: Graphic
+}
+
+impl GraphicBuilder {
+    pub fn color(mut self, color: (f64, f64, f64)) -> GraphicBuilder {
+        self.graphic.color = color;
+        self
+    }
+
+    pub fn viewbox(mut self, viewbox: BoundingBox) -> GraphicBuilder {
+        self.graphic.viewbox = Some(viewbox);
+        self
+    }
+
+    pub fn group<G: Into<Group>>(mut self, group: G) -> GraphicBuilder {
+        self.graphic.groups.push(group.into());
+        self
+    }
+
+    pub fn build(self) -> Graphic {
+        self.graphic
+    }
+}
+
+impl Default for GraphicBuilder {
+    fn default() -> GraphicBuilder {
+        GraphicBuilder {
+            graphic: Graphic {
+                color: (0.0, 0.0, 0.0),
+                groups: Vec::new(),
+                viewbox: None
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct GroupBuilder {
+    group: Group
+}
+
+impl GroupBuilder {
+    pub fn style(mut self, style: Style) -> GroupBuilder {
+        self.group.style = style;
+        self
+    }
+
+    pub fn line_width(mut self, line_width: f64) -> GroupBuilder {
+        self.group.line_width = line_width;
+        self
+    }
+
+    pub fn close(mut self, close: bool) -> GroupBuilder {
+        self.group.close = close;
+        self
+    }
+
+    pub fn segment<S: Into<Segment>>(mut self, segment: S) -> GroupBuilder {
+        self.group.segments.push(segment.into());
+        self
+    }
+
+    pub fn line<P: Into<Point>>(self, start: P, end: P) -> GroupBuilder {
+        self.segment(Line::new(start, end))
+    }
+
+    pub fn arc<P: Into<Point>>(self, center: P, radius: f64, start: f64, end: f64) -> GroupBuilder {
+        self.segment(Arc::new(center, radius, start, end))
+    }
+
+    pub fn oval_arc<P: Into<Point>>(self, center: P, radiusx: f64, radiusy: f64, start: f64, end: f64) -> GroupBuilder {
+        self.segment(OvalArc::new(center, radiusx, radiusy, start, end))
+    }
+
+    pub fn bezier<P: Into<Point>>(self, start: P, control1: P, control2: P, end: P) -> GroupBuilder {
+        self.segment(BezierCurve::new(start, control1, control2, end))
+    }
+
+    pub fn build(self) -> Group {
+        self.group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts;
+    use serde_json;
+    use graphics::{Style, BoundingBox};
+    use super::{Graphic, Group};
+
+    #[test]
+    fn builder_matches_file_format() {
+        let built = Graphic::builder()
+            .color((0.0, 0.5, 1.0))
+            .group(Group::builder()
+                .style(Style::Fill)
+                .line((-0.5, 0.0), (0.5, 0.0))
+                .arc((0.0, 0.0), 0.5, 0.0, consts::PI)
+                .close(true)
+                .build())
+            .group(Group::builder()
+                .line_width(0.05)
+                .bezier((0.0, 0.0), (0.0, 0.5), (0.5, 0.5), (0.5, 0.0))
+                .build())
+            .build();
+
+        let loaded: Graphic = serde_json::from_str(r#"{
+            "color": [0.0, 0.5, 1.0],
+            "groups": [{
+                "segments": [
+                    {"type": "line", "start": "-0.5 | 0", "end": "0.5 | 0"},
+                    {"type": "arc", "radius": 0.5, "start": "0°", "end": "180°"}
+                ],
+                "style": "fill",
+                "close": true
+            }, {
+                "segments": [{
+                    "type": "bezier-curve",
+                    "start": "0 | 0",
+                    "control1": "0 | 0.5",
+                    "control2": "0.5 | 0.5",
+                    "end": "0.5 | 0"
+                }],
+                "line-width": 0.05
+            }]
+        }"#).unwrap();
+
+        assert_eq!(built, loaded);
+    }
+
+    #[test]
+    fn viewbox_needs_area() {
+        let parse = |viewbox: &str| serde_json::from_str::<Graphic>(&format!(
+            r#"{{"color": [0.0, 0.0, 0.0], "groups": [], "viewbox": {}}}"#, viewbox));
+
+        let graphic = parse(r#"["1 | 1", "-1 | -0.5"]"#).unwrap();
+        assert_eq!(graphic.viewbox(), Some(BoundingBox::new((-1.0, -0.5), (1.0, 1.0))));
+
+        for viewbox in &[r#"["-1 | 0", "1 | 0"]"#, r#"["0 | -1", "0 | 1"]"#, r#"["0 | 0", "0 | 0"]"#] {
+            let err = parse(viewbox).err().unwrap();
+            assert!(err.to_string().contains("has no area"), "{}", err);
+        }
+    }
+}
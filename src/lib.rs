@@ -0,0 +1,17 @@
+extern crate num;
+extern crate rand;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+#[cfg(feature = "cairo-rs")]
+extern crate cairo;
+
+pub mod graphics;
+pub mod render;
+pub mod utils;
+pub mod cli;
+mod scan;
+
+pub use graphics::{Graphic, MorphGraphic, LoadError};
+pub use scan::{scan, Entry, Watcher};
@@ -0,0 +1,85 @@
+use cairo::{Context, LineJoin, Matrix, MatrixTrait};
+
+use graphics::{Point, Style, Affine};
+use super::Renderer;
+
+pub struct CairoRenderer<'a> {
+    ctx: &'a Context
+}
+
+impl<'a> CairoRenderer<'a> {
+    pub fn new(ctx: &'a Context) -> CairoRenderer<'a> {
+        ctx.set_line_join(LineJoin::Round);
+        CairoRenderer {ctx}
+    }
+
+    /// Draws everything following through the transform, usually from `View::transform`.
+    pub fn transform(&mut self, affine: &Affine) {
+        let (xx, yx, xy, yy, x0, y0) = affine.entries();
+        self.ctx.transform(Matrix::new(xx, yx, xy, yy, x0, y0));
+    }
+}
+
+impl<'a> Renderer for CairoRenderer<'a> {
+    fn move_to(&mut self, point: Point) {
+        self.ctx.move_to(point.x(), point.y());
+    }
+
+    fn line_to(&mut self, point: Point) {
+        self.ctx.line_to(point.x(), point.y());
+    }
+
+    fn curve_to(&mut self, control1: Point, control2: Point, end: Point) {
+        self.ctx.curve_to(control1.x(), control1.y(),
+            control2.x(), control2.y(), end.x(), end.y());
+    }
+
+    fn arc(&mut self, center: Point, radiusx: f64, radiusy: f64, start: f64, end: f64) {
+        // scaling by zero would leave the context in an error state
+        if radiusx == 0.0 || radiusy == 0.0 {
+            self.ctx.line_to(center.x(), center.y());
+            return;
+        }
+
+        self.ctx.save();
+        self.ctx.translate(center.x(), center.y());
+        self.ctx.scale(radiusx, radiusy);
+
+        if start <= end {
+            self.ctx.arc(0.0, 0.0, 1.0, start, end);
+        } else {
+            self.ctx.arc_negative(0.0, 0.0, 1.0, start, end);
+        }
+
+        self.ctx.restore();
+    }
+
+    fn close_path(&mut self) {
+        self.ctx.close_path();
+    }
+
+    fn set_color(&mut self, color: (f64, f64, f64), alpha: f64) {
+        self.ctx.set_source_rgba(color.0, color.1, color.2, alpha);
+    }
+
+    fn paint(&mut self, style: Style, line_width: f64) {
+        self.ctx.set_line_width(line_width);
+
+        match style {
+            Style::Stroke => self.ctx.stroke(),
+            Style::Fill => self.ctx.fill()
+        }
+    }
+
+    fn text(&mut self, position: Point, size: f64, text: &str) {
+        self.ctx.save();
+        self.ctx.translate(position.x(), position.y());
+        // undo the flipped vertical direction, which would mirror the glyphs
+        self.ctx.scale(1.0, -1.0);
+        self.ctx.set_font_size(size);
+        self.ctx.move_to(0.0, 0.0);
+        self.ctx.show_text(text);
+        self.ctx.new_path();
+        self.ctx.restore();
+    }
+}
@@ -0,0 +1,305 @@
+use std::f64::consts;
+
+use graphics::{Point, BezierCurve, Graphic, MorphGraphic, GroupsRaw, Style, Handle, HandleRef, Editor};
+use super::Renderer;
+
+const HANDLE_COLOR: (f64, f64, f64) = (0.6, 0.6, 0.6);
+const END_COLOR: (f64, f64, f64) = (0.9, 0.3, 0.1);
+const CONTROL_COLOR: (f64, f64, f64) = (0.2, 0.5, 0.9);
+const CLOSE_COLOR: (f64, f64, f64) = (0.1, 0.7, 0.3);
+const LABEL_COLOR: (f64, f64, f64) = (0.2, 0.2, 0.2);
+const SHAPE_COLOR: (f64, f64, f64) = (0.6, 0.2, 0.7);
+/// Sizes in pixels
+const POINT_RADIUS: f64 = 2.5;
+const CLOSE_RADIUS: f64 = 6.0;
+const LABEL_SIZE: f64 = 11.0;
+const GRIP_RADIUS: f64 = 4.0;
+const PAIR_WIDTH: f64 = 2.0;
+/// Opacity of the travel vectors, which would hide the beziers otherwise
+const TRAVEL_ALPHA: f64 = 0.5;
+
+/// Draws the endpoints and control handles of every bezier of the graphic,
+/// labels each segment with its group and segment index as in "1.2", and marks
+/// where closed groups join up. Sizes are given in multiples of one `pixel`.
+pub fn debug_graphic<R: Renderer>(renderer: &mut R, graphic: &Graphic, pixel: f64) {
+    for (group_id, group) in graphic.groups().enumerate() {
+        let mut outline = Vec::new();
+
+        for (segment_id, segment) in group.segments().enumerate() {
+            let beziers = segment.to_beziers(segment.count_beziers());
+
+            for bezier in &beziers {
+                draw_bezier(renderer, bezier, pixel);
+            }
+
+            if let Some(bezier) = beziers.first() {
+                draw_label(renderer, bezier, &format!("{}.{}", group_id, segment_id), pixel);
+            }
+
+            outline.extend(beziers);
+        }
+
+        if group.close() {
+            draw_close(renderer, &outline, pixel);
+        }
+    }
+}
+
+/// Like `debug_graphic`, but for the frame at time `t` of the morph. The labels
+/// count the beziers of each group of the graphic which is drawn at that time.
+pub fn debug_morph<R: Renderer>(renderer: &mut R, morph: &MorphGraphic, groups: &GroupsRaw, t: f64, pixel: f64) {
+    // the same side as `MorphGraphic::draw` takes its groups from
+    let graphic = if t < 0.5 { &morph.start } else { &morph.target };
+    let mut outlines = vec![Vec::new(); graphic.groups().len()];
+
+    for pair in morph.pairs(groups) {
+        let (group_id, position) = if t < 0.5 {
+            (pair.start_group(), pair.start_position())
+        } else {
+            (pair.target_group(), pair.target_position())
+        };
+
+        outlines[group_id].push((position, pair.at(t)));
+    }
+
+    for (group_id, (mut outline, group)) in outlines.into_iter().zip(graphic.groups()).enumerate() {
+        outline.sort_by_key(|&(position, _)| position);
+        let outline = outline.into_iter().map(|(_, bezier)| bezier).collect::<Vec<_>>();
+
+        for (bezier_id, bezier) in outline.iter().enumerate() {
+            draw_bezier(renderer, bezier, pixel);
+            draw_label(renderer, bezier, &format!("{}.{}", group_id, bezier_id), pixel);
+        }
+
+        if group.close() {
+            draw_close(renderer, &outline, pixel);
+        }
+    }
+}
+
+/// Shows which bezier of the start turns into which bezier of the target:
+/// every pair gets a hue of its own on both sides, and a line travels between
+/// their midpoints, with a dot where the pair is at time `t`.
+pub fn debug_pairs<R: Renderer>(renderer: &mut R, morph: &MorphGraphic, groups: &GroupsRaw, t: f64, pixel: f64) {
+    let pairs = morph.pairs(groups);
+
+    for (index, pair) in pairs.iter().enumerate() {
+        let color = hue(index as f64 / pairs.len() as f64);
+        let (start, target) = (pair.start().point_at(0.5), pair.target().point_at(0.5));
+
+        renderer.set_color(color, 1.0);
+        pair.start().draw(renderer, true);
+        renderer.paint(Style::Stroke, PAIR_WIDTH * pixel);
+        pair.target().draw(renderer, true);
+        renderer.paint(Style::Stroke, PAIR_WIDTH * pixel);
+
+        renderer.set_color(color, TRAVEL_ALPHA);
+        renderer.move_to(start);
+        renderer.line_to(target);
+        renderer.paint(Style::Stroke, pixel);
+
+        renderer.set_color(color, 1.0);
+        draw_point(renderer, pair.at(t).point_at(0.5), Style::Fill, pixel);
+    }
+}
+
+/// Returns a saturated color, going once around the color wheel as `hue` goes from 0 to 1.
+fn hue(hue: f64) -> (f64, f64, f64) {
+    let (saturation, value) = (0.8, 0.85);
+    let sector = (hue.fract() * 6.0).floor();
+    let fraction = hue.fract() * 6.0 - sector;
+
+    let low = value * (1.0 - saturation);
+    let falling = value * (1.0 - saturation * fraction);
+    let rising = value * (1.0 - saturation * (1.0 - fraction));
+
+    match sector as u8 {
+        0 => (value, rising, low),
+        1 => (falling, value, low),
+        2 => (low, value, rising),
+        3 => (low, falling, value),
+        4 => (rising, low, value),
+        _ => (value, low, falling)
+    }
+}
+
+/// Marks the handles of the editor, with the `grabbed` one filled.
+pub fn edit_handles<R: Renderer>(renderer: &mut R, editor: &Editor, grabbed: Option<HandleRef>, pixel: f64) {
+    let radius = GRIP_RADIUS * pixel;
+
+    for (handle, position) in editor.handles() {
+        let color = match handle.handle() {
+            Handle::Start | Handle::End => END_COLOR,
+            Handle::Control1 | Handle::Control2 => CONTROL_COLOR,
+            _ => SHAPE_COLOR
+        };
+
+        let style = if Some(handle) == grabbed { Style::Fill } else { Style::Stroke };
+
+        renderer.set_color(color, 1.0);
+        renderer.arc(position, radius, radius, 0.0, 2.0 * consts::PI);
+        renderer.paint(style, pixel);
+    }
+}
+
+fn draw_point<R: Renderer>(renderer: &mut R, point: Point, style: Style, pixel: f64) {
+    let radius = POINT_RADIUS * pixel;
+    renderer.arc(point, radius, radius, 0.0, 2.0 * consts::PI);
+    renderer.paint(style, pixel);
+}
+
+fn draw_bezier<R: Renderer>(renderer: &mut R, bezier: &BezierCurve, pixel: f64) {
+    renderer.set_color(HANDLE_COLOR, 1.0);
+    renderer.move_to(bezier.start());
+    renderer.line_to(bezier.control1());
+    renderer.move_to(bezier.control2());
+    renderer.line_to(bezier.end());
+    renderer.paint(Style::Stroke, pixel);
+
+    renderer.set_color(CONTROL_COLOR, 1.0);
+    draw_point(renderer, bezier.control1(), Style::Stroke, pixel);
+    draw_point(renderer, bezier.control2(), Style::Stroke, pixel);
+
+    renderer.set_color(END_COLOR, 1.0);
+    draw_point(renderer, bezier.start(), Style::Fill, pixel);
+    draw_point(renderer, bezier.end(), Style::Fill, pixel);
+}
+
+/// Writes the label next to the middle of the bezier.
+fn draw_label<R: Renderer>(renderer: &mut R, bezier: &BezierCurve, label: &str, pixel: f64) {
+    let offset = Point::new(POINT_RADIUS, POINT_RADIUS) * pixel;
+
+    renderer.set_color(LABEL_COLOR, 1.0);
+    renderer.text(bezier.point_at(0.5) + offset, LABEL_SIZE * pixel, label);
+}
+
+/// Connects the end of the outline to its start and rings the start.
+fn draw_close<R: Renderer>(renderer: &mut R, outline: &[BezierCurve], pixel: f64) {
+    let (first, last) = match (outline.first(), outline.last()) {
+        (Some(first), Some(last)) => (first.start(), last.end()),
+        _ => return
+    };
+
+    let radius = CLOSE_RADIUS * pixel;
+
+    renderer.set_color(CLOSE_COLOR, 1.0);
+    renderer.move_to(last);
+    renderer.line_to(first);
+    renderer.paint(Style::Stroke, pixel);
+    renderer.arc(first, radius, radius, 0.0, 2.0 * consts::PI);
+    renderer.paint(Style::Stroke, pixel);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use graphics::{Graphic, Group, MorphGraphic};
+    use render::{Recorder, Command};
+    use super::{debug_graphic, debug_morph, debug_pairs, hue};
+
+    fn labels(recorder: &Recorder) -> Vec<String> {
+        recorder.commands().iter()
+            .filter_map(|command| match *command {
+                Command::Text(_, _, ref text) => Some(text.clone()),
+                _ => None
+            })
+            .collect()
+    }
+
+    fn triangle(close: bool) -> Graphic {
+        Graphic::builder()
+            .group(Group::builder()
+                .line((0.0, 0.0), (0.5, 0.0))
+                .line((0.5, 0.0), (0.0, 0.5))
+                .close(close)
+                .build())
+            .group(Group::builder()
+                .arc((0.0, 0.0), 0.5, 0.0, 3.0)
+                .build())
+            .build()
+    }
+
+    #[test]
+    fn debug_graphic_labels_segments() {
+        let mut recorder = Recorder::new();
+        debug_graphic(&mut recorder, &triangle(false), 0.01);
+
+        assert_eq!(labels(&recorder), vec!["0.0", "0.1", "1.0"]);
+    }
+
+    #[test]
+    fn debug_graphic_marks_close_joins() {
+        let mut open = Recorder::new();
+        debug_graphic(&mut open, &triangle(false), 0.01);
+        let mut closed = Recorder::new();
+        debug_graphic(&mut closed, &triangle(true), 0.01);
+
+        // a line from the last end to the first start and a ring around it
+        assert_eq!(closed.commands().len(), open.commands().len() + 6);
+    }
+
+    #[test]
+    fn debug_morph_labels_beziers_of_drawn_side() {
+        let start = Rc::new(triangle(false));
+        let target = Rc::new(Graphic::builder()
+            .group(Group::builder().line((0.0, 0.0), (1.0, 0.0)).build())
+            .build());
+        let (morph, groups) = MorphGraphic::new(start.clone(), target);
+        let count = start.count_beziers();
+
+        let mut recorder = Recorder::new();
+        debug_morph(&mut recorder, &morph, &groups, 0.25, 0.01);
+        let mut drawn = labels(&recorder);
+        drawn.sort();
+        // two lines and an arc of two beziers
+        assert_eq!(drawn, vec!["0.0", "0.1", "1.0", "1.1"]);
+
+        let mut recorder = Recorder::new();
+        debug_morph(&mut recorder, &morph, &groups, 0.75, 0.01);
+        let drawn = labels(&recorder);
+        assert_eq!(drawn.len(), count);
+        assert!(drawn.iter().all(|label| label.starts_with("0.")));
+    }
+
+    #[test]
+    fn debug_pairs_colors_each_pair() {
+        let start = Rc::new(triangle(true));
+        let target = Rc::new(Graphic::builder()
+            .group(Group::builder().line((0.0, 0.0), (1.0, 0.0)).build())
+            .build());
+        let (morph, groups) = MorphGraphic::new(start.clone(), target);
+
+        let mut recorder = Recorder::new();
+        debug_pairs(&mut recorder, &morph, &groups, 0.5, 0.01);
+
+        let mut colors = recorder.commands().iter()
+            .filter_map(|command| match *command {
+                Command::SetColor(color, 1.0) => Some(color),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+        colors.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        colors.dedup();
+        assert_eq!(colors.len(), start.count_beziers());
+
+        let travels = recorder.commands().iter()
+            .filter(|command| match **command {
+                Command::SetColor(_, alpha) => alpha < 1.0,
+                _ => false
+            })
+            .count();
+        assert_eq!(travels, start.count_beziers());
+    }
+
+    #[test]
+    fn hue_goes_around_the_color_wheel() {
+        let close = |a: (f64, f64, f64), b: (f64, f64, f64)| {
+            (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9 && (a.2 - b.2).abs() < 1e-9
+        };
+
+        assert!(close(hue(0.0), (0.85, 0.17, 0.17)));
+        assert!(close(hue(1.0 / 3.0), (0.17, 0.85, 0.17)));
+        assert!(close(hue(2.0 / 3.0), (0.17, 0.17, 0.85)));
+    }
+}
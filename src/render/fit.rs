@@ -0,0 +1,91 @@
+use graphics::{Graphic, MorphGraphic, BoundingBox, Affine};
+
+/// Space around the bounds, relative to their larger side
+const PADDING: f64 = 0.05;
+
+/// Decides which part of the plane is shown.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub enum Fit {
+    /// Show the viewboxes of the graphics, or the viewport if they have none
+    #[default]
+    Viewbox,
+    /// Show everything which is drawn, with some padding
+    Bounds
+}
+
+impl Fit {
+    pub fn graphic_frame(self, graphic: &Graphic) -> BoundingBox {
+        match self {
+            Fit::Viewbox => graphic.viewbox().unwrap_or_else(BoundingBox::viewport),
+            Fit::Bounds => pad(graphic.bounding_box())
+        }
+    }
+
+    /// Returns the frame for the start, the target and every frame in between.
+    pub fn morph_frame(self, morph: &MorphGraphic) -> BoundingBox {
+        match self {
+            Fit::Viewbox => self.graphic_frame(&morph.start).union(self.graphic_frame(&morph.target)),
+            Fit::Bounds => pad(morph.bounding_box())
+        }
+    }
+}
+
+fn pad(bounds: Option<BoundingBox>) -> BoundingBox {
+    match bounds {
+        Some(bounds) => {
+            let size = f64::max(bounds.width(), bounds.height());
+
+            // a single point would be zoomed in infinitely
+            if size > 0.0 {
+                bounds.padded(PADDING * size)
+            } else {
+                BoundingBox::viewport().transform(&Affine::translate(bounds.center()))
+            }
+        },
+        None => BoundingBox::viewport()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use serde_json;
+
+    use graphics::{Graphic, Group, MorphGraphic, BoundingBox, Point};
+    use super::Fit;
+
+    fn line(start: (f64, f64), end: (f64, f64)) -> Graphic {
+        Graphic::builder().group(Group::builder().line(start, end).build()).build()
+    }
+
+    #[test]
+    fn viewbox_is_read_from_both_corners() {
+        let graphic: Graphic = serde_json::from_str(r#"{
+            "color": [0.0, 0.0, 0.0],
+            "groups": [],
+            "viewbox": ["2 | 3", "-2 | -1"]
+        }"#).unwrap();
+
+        let viewbox = BoundingBox::new((-2.0, -1.0), (2.0, 3.0));
+        assert_eq!(graphic.viewbox(), Some(viewbox));
+        assert_eq!(Fit::Viewbox.graphic_frame(&graphic), viewbox);
+        assert_eq!(Fit::Viewbox.graphic_frame(&line((0.0, 0.0), (1.0, 1.0))), BoundingBox::viewport());
+
+        let written = serde_json::to_string(&graphic).unwrap();
+        assert_eq!(serde_json::from_str::<Graphic>(&written).unwrap(), graphic);
+    }
+
+    #[test]
+    fn bounds_cover_start_and_target() {
+        let start = Rc::new(line((0.0, 0.0), (2.0, 0.0)));
+        let target = Rc::new(line((0.0, -1.0), (0.0, 3.0)));
+        let (morph, _) = MorphGraphic::new(start, target);
+
+        let frame = Fit::Bounds.morph_frame(&morph);
+        // padded by a twentieth of the larger side
+        assert_eq!(frame, BoundingBox::new((-0.2, -1.2), (2.2, 3.2)));
+
+        let point = line((0.5, 0.5), (0.5, 0.5));
+        assert_eq!(Fit::Bounds.graphic_frame(&point).center(), Point::new(0.5, 0.5));
+    }
+}
@@ -0,0 +1,24 @@
+use cairo::{Context, Format, ImageSurface};
+
+use graphics::BoundingBox;
+use super::{CairoRenderer, View};
+
+/// Draws offscreen onto a white image of the given size, showing `frame`
+/// the same way as the viewer's canvas.
+pub fn render_image<F>(width: i32, height: i32, frame: &BoundingBox, draw: F) -> ImageSurface
+    where F: FnOnce(&mut CairoRenderer)
+{
+    let surface = ImageSurface::create(Format::ARgb32, width, height);
+
+    {
+        let ctx = Context::new(&surface);
+        ctx.set_source_rgb(1.0, 1.0, 1.0);
+        ctx.paint();
+
+        let mut renderer = CairoRenderer::new(&ctx);
+        renderer.transform(&View::default().transform(width as f64, height as f64, frame));
+        draw(&mut renderer);
+    }
+
+    surface
+}
@@ -0,0 +1,53 @@
+use graphics::{Point, Style};
+
+mod record;
+mod fit;
+mod overlay;
+mod debug;
+mod onion;
+mod sheet;
+mod view;
+#[cfg(feature = "cairo-rs")]
+mod cairo;
+#[cfg(feature = "cairo-rs")]
+mod image;
+
+pub use self::record::{Recorder, Command};
+pub use self::fit::Fit;
+pub use self::overlay::Overlay;
+pub use self::onion::{Fade, onion_skin};
+pub use self::sheet::Sheet;
+pub use self::debug::{debug_graphic, debug_morph, debug_pairs, edit_handles};
+pub use self::view::View;
+#[cfg(feature = "cairo-rs")]
+pub use self::cairo::CairoRenderer;
+#[cfg(feature = "cairo-rs")]
+pub use self::image::render_image;
+
+/// Receives the paths of graphics and paints them, independent of the library
+/// doing the actual drawing.
+///
+/// Like in cairo, adding to the path without a current point starts a new
+/// subpath, and arcs are connected to the current point by a line.
+pub trait Renderer {
+    fn move_to(&mut self, point: Point);
+
+    fn line_to(&mut self, point: Point);
+
+    fn curve_to(&mut self, control1: Point, control2: Point, end: Point);
+
+    /// Adds an elliptic arc around `center`, which goes in negative direction
+    /// if `end` is smaller than `start`.
+    fn arc(&mut self, center: Point, radiusx: f64, radiusy: f64, start: f64, end: f64);
+
+    fn close_path(&mut self);
+
+    fn set_color(&mut self, color: (f64, f64, f64), alpha: f64);
+
+    /// Strokes or fills the current path and starts a new one.
+    fn paint(&mut self, style: Style, line_width: f64);
+
+    /// Writes `text` upright with its baseline starting at `position`, in the current color.
+    /// Renderers which cannot draw text leave it out.
+    fn text(&mut self, _position: Point, _size: f64, _text: &str) {}
+}
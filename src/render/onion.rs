@@ -0,0 +1,141 @@
+use graphics::{Point, Style, MorphGraphic, GroupsRaw};
+use super::Renderer;
+
+/// Opacity of the frames next to the current one, farther ones fade out
+const ONION_ALPHA: f64 = 0.4;
+
+/// Passes everything on to another renderer, but makes it translucent.
+pub struct Fade<'a, R: 'a> {
+    renderer: &'a mut R,
+    alpha: f64
+}
+
+impl<'a, R: Renderer> Fade<'a, R> {
+    pub fn new(renderer: &'a mut R, alpha: f64) -> Fade<'a, R> {
+        Fade {renderer, alpha}
+    }
+}
+
+impl<'a, R: Renderer> Renderer for Fade<'a, R> {
+    fn move_to(&mut self, point: Point) {
+        self.renderer.move_to(point);
+    }
+
+    fn line_to(&mut self, point: Point) {
+        self.renderer.line_to(point);
+    }
+
+    fn curve_to(&mut self, control1: Point, control2: Point, end: Point) {
+        self.renderer.curve_to(control1, control2, end);
+    }
+
+    fn arc(&mut self, center: Point, radiusx: f64, radiusy: f64, start: f64, end: f64) {
+        self.renderer.arc(center, radiusx, radiusy, start, end);
+    }
+
+    fn close_path(&mut self) {
+        self.renderer.close_path();
+    }
+
+    fn set_color(&mut self, color: (f64, f64, f64), alpha: f64) {
+        self.renderer.set_color(color, alpha * self.alpha);
+    }
+
+    fn paint(&mut self, style: Style, line_width: f64) {
+        self.renderer.paint(style, line_width);
+    }
+
+    fn text(&mut self, position: Point, size: f64, text: &str) {
+        self.renderer.text(position, size, text);
+    }
+}
+
+/// Draws `count` translucent frames of the morph on either side of time `t`,
+/// `spacing` apart. Frames before the start or after the target are left out.
+pub fn onion_skin<R: Renderer>(renderer: &mut R, morph: &MorphGraphic, groups: &GroupsRaw,
+    t: f64, count: usize, spacing: f64)
+{
+    // the farthest frames first, so that the closer ones end up on top
+    for distance in (1..count + 1).rev() {
+        let alpha = ONION_ALPHA * (count + 1 - distance) as f64 / (count + 1) as f64;
+
+        for &frame in &[t - distance as f64 * spacing, t + distance as f64 * spacing] {
+            if !(0.0..=1.0).contains(&frame) {
+                continue;
+            }
+
+            morph.draw_frame(&mut Fade::new(&mut *renderer, alpha), groups, frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use graphics::{Graphic, Group, MorphGraphic, Style};
+    use render::{Renderer, Recorder, Command};
+    use super::{Fade, onion_skin};
+
+    #[test]
+    fn fade_scales_alpha() {
+        let mut recorder = Recorder::new();
+
+        {
+            let mut fade = Fade::new(&mut recorder, 0.5);
+            fade.set_color((1.0, 0.0, 0.0), 0.5);
+            fade.paint(Style::Fill, 0.0);
+        }
+
+        assert_eq!(recorder.commands(), &[
+            Command::SetColor((1.0, 0.0, 0.0), 0.25),
+            Command::Paint(Style::Fill, 0.0)
+        ]);
+    }
+
+    fn frames(t: f64) -> usize {
+        let line = |end| Rc::new(Graphic::builder()
+            .group(Group::builder().line((0.0, 0.0), end).build())
+            .build());
+        let (morph, groups) = MorphGraphic::new(line((1.0, 0.0)), line((0.0, 1.0)));
+
+        let mut single = Recorder::new();
+        morph.draw(&mut single, groups.link(&morph), 0.5);
+        let mut onion = Recorder::new();
+        onion_skin(&mut onion, &morph, &groups, t, 2, 0.1);
+
+        assert!(onion.commands().iter().all(|command| match *command {
+            Command::SetColor(_, alpha) => alpha < 0.5,
+            _ => true
+        }));
+
+        onion.commands().len() / single.commands().len()
+    }
+
+    #[test]
+    fn onion_skin_draws_frames_within_morph() {
+        assert_eq!(frames(0.5), 4);
+        assert_eq!(frames(0.05), 2);
+        assert_eq!(frames(1.0), 2);
+    }
+
+    #[test]
+    fn onion_skin_draws_graphics_at_ends() {
+        let start = Rc::new(Graphic::builder()
+            .group(Group::builder().arc((0.0, 0.0), 0.5, 0.0, 1.0).build())
+            .build());
+        let target = Rc::new(Graphic::builder()
+            .group(Group::builder().line((0.0, 0.0), (1.0, 0.0)).build())
+            .build());
+        let (morph, groups) = MorphGraphic::new(start.clone(), target.clone());
+
+        let mut expected = Recorder::new();
+        start.draw(&mut Fade::new(&mut expected, 0.2));
+        target.draw(&mut Fade::new(&mut expected, 0.2));
+
+        let mut onion = Recorder::new();
+        onion_skin(&mut onion, &morph, &groups, 0.5, 1, 0.5);
+
+        assert_eq!(onion.commands(), expected.commands());
+    }
+}
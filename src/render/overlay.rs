@@ -0,0 +1,147 @@
+use std::f64::consts;
+
+use graphics::{Point, BoundingBox, Style};
+use super::Renderer;
+
+/// More lines than this would only fill the canvas with gray
+const MAX_LINES: f64 = 500.0;
+const GRID_COLOR: (f64, f64, f64) = (0.85, 0.85, 0.85);
+const AXIS_COLOR: (f64, f64, f64) = (0.7, 0.7, 0.7);
+const FRAME_COLOR: (f64, f64, f64) = (0.5, 0.6, 0.9);
+
+/// Guides drawn beneath the graphics, which help to place points in either
+/// notation of the file format.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Overlay {
+    grid: Option<f64>,
+    polar: Option<(f64, f64)>,
+    frame: bool
+}
+
+impl Overlay {
+    /// Adds a cartesian grid with lines `spacing` apart.
+    pub fn grid(mut self, spacing: f64) -> Overlay {
+        self.grid = Some(spacing);
+        self
+    }
+
+    /// Adds circles `radius_step` apart around the origin, crossed by spokes
+    /// every `angle_step` degrees.
+    pub fn polar(mut self, radius_step: f64, angle_step: f64) -> Overlay {
+        self.polar = Some((radius_step, angle_step));
+        self
+    }
+
+    /// Adds the square from -1 | -1 to 1 | 1.
+    pub fn frame(mut self) -> Overlay {
+        self.frame = true;
+        self
+    }
+
+    /// Draws the guides covering the `visible` part of the plane, with lines
+    /// one `pixel` wide.
+    pub fn draw<R: Renderer>(&self, renderer: &mut R, visible: &BoundingBox, pixel: f64) {
+        if let Some(spacing) = self.grid {
+            draw_grid(renderer, visible, spacing, pixel);
+        }
+
+        if let Some((radius_step, angle_step)) = self.polar {
+            draw_polar(renderer, visible, radius_step, angle_step, pixel);
+        }
+
+        if self.frame {
+            let viewport = BoundingBox::viewport();
+
+            renderer.set_color(FRAME_COLOR, 1.0);
+            renderer.move_to(viewport.min());
+            renderer.line_to(Point::new(viewport.max().x(), viewport.min().y()));
+            renderer.line_to(viewport.max());
+            renderer.line_to(Point::new(viewport.min().x(), viewport.max().y()));
+            renderer.close_path();
+            renderer.paint(Style::Stroke, pixel);
+        }
+    }
+}
+
+/// Returns the multiples of `step` between `min` and `max`.
+fn multiples(min: f64, max: f64, step: f64) -> Vec<f64> {
+    if step <= 0.0 || (max - min) / step > MAX_LINES {
+        return Vec::new();
+    }
+
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+
+    (first..last + 1).map(|index| index as f64 * step).collect()
+}
+
+fn draw_grid<R: Renderer>(renderer: &mut R, visible: &BoundingBox, spacing: f64, pixel: f64) {
+    let (min, max) = (visible.min(), visible.max());
+
+    for x in multiples(min.x(), max.x(), spacing) {
+        renderer.set_color(if x == 0.0 { AXIS_COLOR } else { GRID_COLOR }, 1.0);
+        renderer.move_to(Point::new(x, min.y()));
+        renderer.line_to(Point::new(x, max.y()));
+        renderer.paint(Style::Stroke, pixel);
+    }
+
+    for y in multiples(min.y(), max.y(), spacing) {
+        renderer.set_color(if y == 0.0 { AXIS_COLOR } else { GRID_COLOR }, 1.0);
+        renderer.move_to(Point::new(min.x(), y));
+        renderer.line_to(Point::new(max.x(), y));
+        renderer.paint(Style::Stroke, pixel);
+    }
+}
+
+fn draw_polar<R: Renderer>(renderer: &mut R, visible: &BoundingBox, radius_step: f64, angle_step: f64, pixel: f64) {
+    let (min, max) = (visible.min(), visible.max());
+    let corners = [min, max, Point::new(min.x(), max.y()), Point::new(max.x(), min.y())];
+    let reach = corners.iter().map(Point::length).fold(0.0, f64::max);
+
+    renderer.set_color(GRID_COLOR, 1.0);
+
+    for radius in multiples(radius_step, reach, radius_step) {
+        renderer.arc(Point::default(), radius, radius, 0.0, 2.0 * consts::PI);
+        renderer.paint(Style::Stroke, pixel);
+    }
+
+    for angle in multiples(0.0, 360.0 - angle_step / 2.0, angle_step) {
+        renderer.move_to(Point::default());
+        renderer.line_to(Point::default().on_circle(reach, angle.to_radians()));
+        renderer.paint(Style::Stroke, pixel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graphics::BoundingBox;
+    use render::{Recorder, Command};
+    use super::Overlay;
+
+    fn paints(overlay: Overlay, visible: BoundingBox) -> usize {
+        let mut recorder = Recorder::new();
+        overlay.draw(&mut recorder, &visible, 0.01);
+
+        recorder.commands().iter()
+            .filter(|command| matches!(**command, Command::Paint(..)))
+            .count()
+    }
+
+    #[test]
+    fn overlay_draws_enabled_guides() {
+        let visible = BoundingBox::new((-1.2, -0.6), (1.2, 0.6));
+
+        assert_eq!(paints(Overlay::default(), visible), 0);
+        // 5 vertical and 3 horizontal lines
+        assert_eq!(paints(Overlay::default().grid(0.5), visible), 8);
+        // the farthest corner is 1.34 away, with 24 spokes every 15 degrees
+        assert_eq!(paints(Overlay::default().polar(0.25, 15.0), visible), 5 + 24);
+        assert_eq!(paints(Overlay::default().frame(), visible), 1);
+    }
+
+    #[test]
+    fn overlay_skips_dense_grids() {
+        let visible = BoundingBox::new((-100.0, -100.0), (100.0, 100.0));
+        assert_eq!(paints(Overlay::default().grid(0.1), visible), 0);
+    }
+}
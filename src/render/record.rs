@@ -0,0 +1,127 @@
+use std::fmt;
+
+use graphics::{Point, Style};
+use super::Renderer;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    MoveTo(Point),
+    LineTo(Point),
+    CurveTo(Point, Point, Point),
+    Arc {
+        center: Point,
+        radiusx: f64,
+        radiusy: f64,
+        start: f64,
+        end: f64
+    },
+    ClosePath,
+    SetColor((f64, f64, f64), f64),
+    Paint(Style, f64),
+    Text(Point, f64, String)
+}
+
+/// Formats a number with a fixed precision, so that rounding noise does not
+/// show up in comparisons.
+fn number(value: f64) -> String {
+    let formatted = format!("{:.6}", value);
+
+    // rounding may leave a negative zero behind
+    if formatted == "-0.000000" {
+        "0.000000".into()
+    } else {
+        formatted
+    }
+}
+
+fn point(point: Point) -> String {
+    format!("{} {}", number(point.x()), number(point.y()))
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::MoveTo(to) => write!(f, "move_to {}", point(to)),
+            Command::LineTo(to) => write!(f, "line_to {}", point(to)),
+            Command::CurveTo(control1, control2, end) =>
+                write!(f, "curve_to {} {} {}", point(control1), point(control2), point(end)),
+            Command::Arc {center, radiusx, radiusy, start, end} =>
+                write!(f, "arc {} {} {} {} {}", point(center),
+                    number(radiusx), number(radiusy), number(start), number(end)),
+            Command::ClosePath => f.write_str("close_path"),
+            Command::SetColor(color, alpha) =>
+                write!(f, "set_color {} {} {} {}", number(color.0),
+                    number(color.1), number(color.2), number(alpha)),
+            Command::Paint(style, line_width) => {
+                let style = match style {
+                    Style::Stroke => "stroke",
+                    Style::Fill => "fill"
+                };
+
+                write!(f, "paint {} {}", style, number(line_width))
+            },
+            Command::Text(position, size, ref text) =>
+                write!(f, "text {} {} {:?}", point(position), number(size), text)
+        }
+    }
+}
+
+/// Remembers every call instead of drawing, to inspect what a graphic emits.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Recorder {
+    commands: Vec<Command>
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+}
+
+impl fmt::Display for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for command in &self.commands {
+            writeln!(f, "{}", command)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Renderer for Recorder {
+    fn move_to(&mut self, point: Point) {
+        self.commands.push(Command::MoveTo(point));
+    }
+
+    fn line_to(&mut self, point: Point) {
+        self.commands.push(Command::LineTo(point));
+    }
+
+    fn curve_to(&mut self, control1: Point, control2: Point, end: Point) {
+        self.commands.push(Command::CurveTo(control1, control2, end));
+    }
+
+    fn arc(&mut self, center: Point, radiusx: f64, radiusy: f64, start: f64, end: f64) {
+        self.commands.push(Command::Arc {center, radiusx, radiusy, start, end});
+    }
+
+    fn close_path(&mut self) {
+        self.commands.push(Command::ClosePath);
+    }
+
+    fn set_color(&mut self, color: (f64, f64, f64), alpha: f64) {
+        self.commands.push(Command::SetColor(color, alpha));
+    }
+
+    fn paint(&mut self, style: Style, line_width: f64) {
+        self.commands.push(Command::Paint(style, line_width));
+    }
+
+    fn text(&mut self, position: Point, size: f64, text: &str) {
+        self.commands.push(Command::Text(position, size, text.to_owned()));
+    }
+}
@@ -0,0 +1,123 @@
+use std::cmp;
+use std::ops::Range;
+
+use graphics::{Point, BoundingBox};
+
+/// Lays out cells of the same size in rows from left to right, like the
+/// thumbnails of a contact sheet. Everything is measured in pixels.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Sheet {
+    columns: usize,
+    cell_width: f64,
+    cell_height: f64
+}
+
+impl Sheet {
+    /// Creates a sheet with the given number of columns, but at least one.
+    pub fn new(columns: usize, cell_width: f64, cell_height: f64) -> Sheet {
+        Sheet {
+            columns: cmp::max(columns, 1),
+            cell_width,
+            cell_height
+        }
+    }
+
+    /// Creates a sheet with as many columns as fit into `width`.
+    pub fn fitting(width: f64, cell_width: f64, cell_height: f64) -> Sheet {
+        Sheet::new((width / cell_width).floor() as usize, cell_width, cell_height)
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Returns the number of rows needed for `count` cells.
+    pub fn rows(&self, count: usize) -> usize {
+        count.div_ceil(self.columns)
+    }
+
+    pub fn width(&self) -> f64 {
+        self.columns as f64 * self.cell_width
+    }
+
+    /// Returns the height needed for `count` cells.
+    pub fn height(&self, count: usize) -> f64 {
+        self.rows(count) as f64 * self.cell_height
+    }
+
+    /// Returns the row and column of the cell at `index`.
+    pub fn locate(&self, index: usize) -> (usize, usize) {
+        (index / self.columns, index % self.columns)
+    }
+
+    /// Returns the area covered by the cell at `index`.
+    pub fn cell(&self, index: usize) -> BoundingBox {
+        let (row, column) = self.locate(index);
+        let corner = Point::new(column as f64 * self.cell_width, row as f64 * self.cell_height);
+
+        BoundingBox::new(corner, corner + Point::new(self.cell_width, self.cell_height))
+    }
+
+    /// Returns the index of the cell under `point`, if there is one of the
+    /// first `count` cells.
+    pub fn index_at(&self, point: Point, count: usize) -> Option<usize> {
+        if point.x() < 0.0 || point.y() < 0.0 || point.x() >= self.width() {
+            return None;
+        }
+
+        let row = (point.y() / self.cell_height) as usize;
+        let column = (point.x() / self.cell_width) as usize;
+        let index = row * self.columns + column;
+
+        if index < count {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the indices of the first `count` cells which lie in the rows
+    /// between `top` and `bottom`, to only draw what is scrolled into view.
+    pub fn visible(&self, top: f64, bottom: f64, count: usize) -> Range<usize> {
+        let first = (top.max(0.0) / self.cell_height).floor() as usize;
+        let last = (bottom.max(0.0) / self.cell_height).ceil() as usize;
+
+        cmp::min(first * self.columns, count)..cmp::min(last * self.columns, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graphics::{Point, BoundingBox};
+    use super::Sheet;
+
+    #[test]
+    fn sheet_wraps_cells_into_rows() {
+        let sheet = Sheet::fitting(350.0, 100.0, 120.0);
+
+        assert_eq!(sheet.columns(), 3);
+        assert_eq!(sheet.rows(7), 3);
+        assert_eq!(sheet.height(7), 360.0);
+        assert_eq!(sheet.cell(4), BoundingBox::new((100.0, 120.0), (200.0, 240.0)));
+        assert_eq!(Sheet::fitting(50.0, 100.0, 120.0).columns(), 1);
+    }
+
+    #[test]
+    fn sheet_finds_cells() {
+        let sheet = Sheet::new(3, 100.0, 120.0);
+
+        assert_eq!(sheet.index_at(Point::new(150.0, 130.0), 7), Some(4));
+        assert_eq!(sheet.index_at(Point::new(150.0, 250.0), 7), None);
+        assert_eq!(sheet.index_at(Point::new(320.0, 10.0), 7), None);
+        assert_eq!(sheet.index_at(Point::new(-1.0, 10.0), 7), None);
+    }
+
+    #[test]
+    fn sheet_limits_cells_to_visible_rows() {
+        let sheet = Sheet::new(3, 100.0, 120.0);
+
+        assert_eq!(sheet.visible(0.0, 100.0, 7), 0..3);
+        assert_eq!(sheet.visible(130.0, 250.0, 7), 3..7);
+        assert_eq!(sheet.visible(500.0, 600.0, 7), 7..7);
+    }
+}
@@ -0,0 +1,111 @@
+use graphics::{Point, BoundingBox, Affine};
+
+/// Zoom and pan of a canvas, on top of fitting a frame into it. Both are
+/// applied in pixels, so that they stay put when the frame changes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct View {
+    zoom: f64,
+    offset: Point
+}
+
+impl View {
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn offset(&self) -> Point {
+        self.offset
+    }
+
+    /// Returns the transform from the plane onto a canvas of the given size,
+    /// which shows `frame` as large as it fits before zooming and panning.
+    pub fn transform(&self, width: f64, height: f64, frame: &BoundingBox) -> Affine {
+        // a frame without width or height is fitted along the other direction,
+        // one without either, like a single point, is not scaled at all
+        let scale = [width / frame.width(), height / frame.height()].iter()
+            .cloned()
+            .filter(|scale| scale.is_finite())
+            .fold(f64::INFINITY, f64::min);
+        let scale = if scale.is_finite() && scale > 0.0 { scale } else { 1.0 };
+
+        // align the center of the frame to the middle and change vertical direction
+        Affine::translate(Point::default() - frame.center())
+            .then(&Affine::scale(scale, -scale))
+            .then(&Affine::translate((width / 2.0, height / 2.0)))
+            .then(&Affine::scale(self.zoom, self.zoom))
+            .then(&Affine::translate(self.offset))
+    }
+
+    /// Zooms by `factor`, keeping the point under the cursor in place.
+    pub fn zoom_at(&mut self, factor: f64, cursor: Point) {
+        self.zoom *= factor;
+        self.offset = cursor - factor * (cursor - self.offset);
+    }
+
+    pub fn pan(&mut self, delta: Point) {
+        self.offset += delta;
+    }
+
+    pub fn reset(&mut self) {
+        *self = View::default();
+    }
+}
+
+impl Default for View {
+    fn default() -> View {
+        View {
+            zoom: 1.0,
+            offset: Point::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graphics::{Point, BoundingBox};
+    use super::View;
+
+    fn assert_close(actual: Point, expected: Point) {
+        assert!(actual.distance(expected) < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn default_view_fits_frame() {
+        let transform = View::default().transform(400.0, 200.0, &BoundingBox::viewport());
+
+        assert_close(transform.apply(Point::new(0.0, 0.0)), Point::new(200.0, 100.0));
+        assert_close(transform.apply(Point::new(-1.0, 1.0)), Point::new(100.0, 0.0));
+        assert_close(transform.apply(Point::new(1.0, -1.0)), Point::new(300.0, 200.0));
+    }
+
+    #[test]
+    fn zoom_keeps_cursor_in_place() {
+        let frame = BoundingBox::viewport();
+        let cursor = Point::new(120.0, 40.0);
+        let mut view = View::default();
+        view.pan(Point::new(-30.0, 15.0));
+
+        let under_cursor = view.transform(400.0, 200.0, &frame).invert().unwrap().apply(cursor);
+        view.zoom_at(1.5, cursor);
+        view.zoom_at(1.5, cursor);
+
+        assert_eq!(view.zoom(), 2.25);
+        assert_close(view.transform(400.0, 200.0, &frame).apply(under_cursor), cursor);
+
+        view.reset();
+        assert_eq!(view, View::default());
+    }
+
+    #[test]
+    fn degenerate_frames() {
+        let line = BoundingBox::new((-1.0, 0.0), (1.0, 0.0));
+        let transform = View::default().transform(400.0, 200.0, &line);
+        assert_close(transform.apply(Point::new(1.0, 0.0)), Point::new(400.0, 100.0));
+
+        let point = BoundingBox::new((0.5, 0.5), (0.5, 0.5));
+        let transform = View::default().transform(400.0, 200.0, &point);
+        assert_close(transform.apply(Point::new(0.5, 0.5)), Point::new(200.0, 100.0));
+        assert_close(transform.apply(Point::new(1.5, 0.5)), Point::new(201.0, 100.0));
+        assert!(transform.invert().is_some());
+    }
+}
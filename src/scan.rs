@@ -0,0 +1,374 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::collections::HashMap;
+
+use graphics::{Graphic, LoadError};
+
+/// Extensions of the files which are loaded as graphics
+const EXTENSIONS: &[&str] = &["json"];
+
+/// A file in a directory listing names or `*` patterns to skip, one per line,
+/// which apply to the directory and everything below it
+const IGNORE_FILE: &str = ".phintignore";
+
+/// A file found in a directory of graphics.
+#[derive(Debug)]
+pub struct Entry {
+    pub name: String,
+    /// The subdirectory the file was found in, relative to the scanned
+    /// directory and separated by `/`, or empty at the top level
+    pub category: String,
+    pub path: PathBuf,
+    pub graphic: Result<Graphic, LoadError>
+}
+
+/// Loads the graphics in the directory and its subdirectories, named after
+/// their file stems and sorted by category and name in natural order.
+pub fn scan<P: AsRef<Path>>(path: P) -> io::Result<Vec<Entry>> {
+    let mut files = Vec::new();
+    walk(path.as_ref(), String::new(), &[], &mut files, &mut Vec::new())?;
+
+    let mut entries = files.into_iter().map(|(path, category)| {
+        let name = path.file_stem()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let graphic = Graphic::load(&path);
+
+        Entry {name, category, path, graphic}
+    }).collect::<Vec<_>>();
+
+    entries.sort_by(|a, b| natural_order(&a.category, &b.category)
+        .then_with(|| natural_order(&a.name, &b.name)));
+
+    Ok(entries)
+}
+
+/// Collects the graphic files below `path` along with their categories,
+/// leaving out hidden files, symlinked directories and those matching `ignored`.
+/// The ignore files which were read go to `ignore_files`.
+fn walk(path: &Path, category: String, ignored: &[String], files: &mut Vec<(PathBuf, String)>,
+    ignore_files: &mut Vec<PathBuf>) -> io::Result<()>
+{
+    let ignore_file = path.join(IGNORE_FILE);
+    let mut ignored = ignored.to_vec();
+
+    if let Some(patterns) = read_ignore_file(&ignore_file)? {
+        ignored.extend(patterns);
+        ignore_files.push(ignore_file);
+    }
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = path.file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+
+        if name.starts_with('.') || ignored.iter().any(|pattern| matches(pattern, &name)) {
+            continue;
+        }
+
+        // symlinked directories are left out, as they might lead back up
+        if entry.file_type()?.is_dir() {
+            let category = if category.is_empty() { name } else { format!("{}/{}", category, name) };
+            walk(&path, category, &ignored, files, ignore_files)?;
+        } else if path.is_file() {
+            let known = match path.extension() {
+                Some(extension) => EXTENSIONS.contains(&extension.to_string_lossy().to_lowercase().as_str()),
+                None => false
+            };
+
+            if known {
+                files.push((path, category.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_ignore_file(path: &Path) -> io::Result<Option<Vec<String>>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err)
+    };
+
+    let mut patterns = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if !line.is_empty() && !line.starts_with('#') {
+            patterns.push(line.to_string());
+        }
+    }
+
+    Ok(Some(patterns))
+}
+
+/// Matches a file name against a pattern in which `*` stands for any text.
+fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+
+    if !name.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &name[first.len()..];
+    let mut parts = parts.collect::<Vec<_>>();
+
+    // without a `*` the name has to match as a whole
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty()
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Compares names ignoring case and taking runs of digits as numbers, so
+/// that `leaf2` comes before `leaf10`.
+fn natural_order(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        let ordering = match (a_chars.peek().cloned(), b_chars.peek().cloned()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a_chars);
+                let y = take_number(&mut b_chars);
+
+                // compare by length first, which also works beyond the range of integers
+                x.len().cmp(&y.len()).then_with(|| x.cmp(&y))
+            },
+            (Some(x), Some(y)) => {
+                a_chars.next();
+                b_chars.next();
+                x.to_lowercase().cmp(y.to_lowercase())
+            }
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Takes the run of digits at the start of `chars`, without leading zeros.
+fn take_number<I: Iterator<Item = char>>(chars: &mut ::std::iter::Peekable<I>) -> String {
+    let mut number = String::new();
+
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+
+        if !(number.is_empty() && c == '0') {
+            number.push(c);
+        }
+
+        chars.next();
+    }
+
+    number
+}
+
+/// Notices when graphics or ignore files in a directory or its subdirectories
+/// are added, removed or modified, by comparing their modification times and
+/// sizes between polls.
+#[derive(Debug)]
+pub struct Watcher {
+    path: PathBuf,
+    files: HashMap<PathBuf, (SystemTime, u64)>
+}
+
+impl Watcher {
+    /// Starts watching the directory, taking the files in it as they are now.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Watcher> {
+        let path = path.as_ref().to_owned();
+        let files = Watcher::files(&path)?;
+
+        Ok(Watcher {path, files})
+    }
+
+    /// Returns whether any file changed since the last poll.
+    pub fn poll(&mut self) -> io::Result<bool> {
+        let files = Watcher::files(&self.path)?;
+        let changed = files != self.files;
+        self.files = files;
+
+        Ok(changed)
+    }
+
+    fn files(path: &Path) -> io::Result<HashMap<PathBuf, (SystemTime, u64)>> {
+        let mut paths = Vec::new();
+        let mut ignore_files = Vec::new();
+        walk(path, String::new(), &[], &mut paths, &mut ignore_files)?;
+
+        // changed ignore files change which graphics there are
+        stamps(paths.into_iter().map(|(path, _)| path).chain(ignore_files))
+    }
+}
+
+/// Returns when the files were last modified and their sizes. Files which were
+/// removed since the directory was read are left out.
+fn stamps<I: IntoIterator<Item = PathBuf>>(paths: I) -> io::Result<HashMap<PathBuf, (SystemTime, u64)>> {
+    let mut stamps = HashMap::new();
+
+    for path in paths {
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err)
+        };
+
+        stamps.insert(path, (metadata.modified()?, metadata.len()));
+    }
+
+    Ok(stamps)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    use std::cmp::Ordering;
+
+    use utils::TestDir;
+    use super::{scan, stamps, Watcher, natural_order, matches};
+
+    #[test]
+    fn scan_empty_directory() {
+        let dir = TestDir::new("scan_empty_directory");
+
+        assert!(scan(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn scan_subdirectories_in_order() {
+        let dir = TestDir::new("scan_subdirectories_in_order");
+        let path = dir.path();
+        fs::create_dir_all(path.join("leaves").join("small")).unwrap();
+        fs::create_dir_all(path.join("drafts")).unwrap();
+
+        for file in &["leaf10.json", "Leaf2.json", "notes.txt", "leaves/oak.json", "leaves/small/birch.JSON",
+            "leaves/old.json", "drafts/sketch.json"]
+        {
+            File::create(path.join(file)).unwrap().write_all(b"{}").unwrap();
+        }
+
+        File::create(path.join(".phintignore")).unwrap().write_all(b"# unfinished\ndrafts\n").unwrap();
+        File::create(path.join("leaves").join(".phintignore")).unwrap().write_all(b"old*").unwrap();
+
+        let found = scan(path).unwrap().into_iter()
+            .map(|entry| (entry.category, entry.name))
+            .collect::<Vec<_>>();
+
+        assert_eq!(found, vec![
+            (String::new(), "Leaf2".to_string()),
+            (String::new(), "leaf10".to_string()),
+            ("leaves".to_string(), "oak".to_string()),
+            ("leaves/small".to_string(), "birch".to_string())
+        ]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_skips_symlinked_directories() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TestDir::new("scan_skips_symlinked_directories");
+        let path = dir.path();
+        fs::create_dir_all(path.join("leaves")).unwrap();
+        File::create(path.join("leaves").join("oak.json")).unwrap().write_all(b"{}").unwrap();
+
+        // a loop back up, and a link to a file which is followed
+        symlink(path, path.join("leaves").join("up")).unwrap();
+        symlink(path.join("leaves").join("oak.json"), path.join("linked.json")).unwrap();
+
+        let found = scan(path).unwrap().into_iter()
+            .map(|entry| (entry.category, entry.name))
+            .collect::<Vec<_>>();
+
+        assert_eq!(found, vec![
+            (String::new(), "linked".to_string()),
+            ("leaves".to_string(), "oak".to_string())
+        ]);
+        assert!(!Watcher::new(path).unwrap().poll().unwrap());
+    }
+
+    #[test]
+    fn natural_order_compares_numbers() {
+        assert_eq!(natural_order("leaf2", "leaf10"), Ordering::Less);
+        assert_eq!(natural_order("Leaf2", "leaf02"), Ordering::Less);
+        assert_eq!(natural_order("b", "A"), Ordering::Greater);
+        assert_eq!(natural_order("leaf", "leaf1"), Ordering::Less);
+    }
+
+    #[test]
+    fn ignore_patterns() {
+        assert!(matches("drafts", "drafts"));
+        assert!(!matches("drafts", "drafts2"));
+        assert!(matches("*.bak", "leaf.bak"));
+        assert!(matches("old*", "old"));
+        assert!(matches("a*b*c", "a-b-c"));
+        assert!(!matches("a*a", "a"));
+    }
+
+    #[test]
+    fn watcher_notices_changes() {
+        let dir = TestDir::new("watcher_notices_changes");
+        let path = dir.path();
+
+        let mut watcher = Watcher::new(path).unwrap();
+        assert!(!watcher.poll().unwrap());
+
+        File::create(path.join("a.json")).unwrap().write_all(b"{}").unwrap();
+        assert!(watcher.poll().unwrap());
+        assert!(!watcher.poll().unwrap());
+
+        File::create(path.join("a.json")).unwrap().write_all(b"{ }").unwrap();
+        assert!(watcher.poll().unwrap());
+
+        fs::create_dir_all(path.join("nested")).unwrap();
+        File::create(path.join("nested").join("b.json")).unwrap().write_all(b"{}").unwrap();
+        File::create(path.join("notes.txt")).unwrap().write_all(b"{}").unwrap();
+        assert!(watcher.poll().unwrap());
+        assert!(!watcher.poll().unwrap());
+
+        fs::remove_file(path.join("a.json")).unwrap();
+        assert!(watcher.poll().unwrap());
+        assert!(!watcher.poll().unwrap());
+
+        File::create(path.join(".phintignore")).unwrap().write_all(b"a.json").unwrap();
+        assert!(watcher.poll().unwrap());
+        File::create(path.join(".phintignore")).unwrap().write_all(b"# only a comment").unwrap();
+        assert!(watcher.poll().unwrap());
+        assert!(!watcher.poll().unwrap());
+    }
+
+    #[test]
+    fn stamps_leave_out_removed_files() {
+        let dir = TestDir::new("stamps_leave_out_removed_files");
+        let path = dir.path();
+        File::create(path.join("oak.json")).unwrap().write_all(b"{}").unwrap();
+
+        let stamps = stamps(vec![path.join("oak.json"), path.join("removed.json")]).unwrap();
+        assert_eq!(stamps.keys().collect::<Vec<_>>(), vec![&path.join("oak.json")]);
+    }
+}
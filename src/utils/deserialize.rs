@@ -1,12 +1,39 @@
 use std::fmt;
+use std::cell::RefCell;
 use serde::de::{self, Error, Unexpected, Deserialize, Deserializer, Visitor, MapAccess};
 
-use graphics::{Style, Point};
+use graphics::{Style, Point, BoundingBox};
 
 mod grammar {
     include!(concat!(env!("OUT_DIR"), "/deserialize.rs"));
 }
 
+thread_local! {
+    // serde only keeps the message of custom errors, so the failing string and
+    // the position inside of it are passed on to `LoadError` through here
+    static GRAMMAR_FAILURE: RefCell<Option<(String, usize)>> = const { RefCell::new(None) };
+}
+
+/// Returns and resets the coordinate string which failed to parse last,
+/// together with the byte offset of the failure.
+pub fn take_grammar_failure() -> Option<(String, usize)> {
+    GRAMMAR_FAILURE.with(|failure| failure.borrow_mut().take())
+}
+
+fn grammar_error<E>(kind: &str, value: &str, err: grammar::ParseError) -> E
+    where E: de::Error
+{
+    GRAMMAR_FAILURE.with(|failure| {
+        *failure.borrow_mut() = Some((value.to_owned(), err.offset));
+    });
+
+    let mut expected = err.expected.into_iter().collect::<Vec<_>>();
+    expected.sort();
+
+    E::custom(format_args!("invalid {} `{}`, expected one of {}",
+        kind, value, expected.join(", ")))
+}
+
 struct LineWidthVisitor;
 
 impl<'de> Visitor<'de> for LineWidthVisitor {
@@ -85,7 +112,7 @@ impl<'de> Visitor<'de> for AngleVisitor {
         where E: de::Error
     {
         // use custom grammar for strings
-        grammar::angle(value).map_err(de::Error::custom)
+        grammar::angle(value).map_err(|err| grammar_error("angle", value, err))
     }
 }
 
@@ -137,7 +164,7 @@ impl<'de> Visitor<'de> for PointVisitor {
     fn visit_str<E>(self, value: &str) -> Result<Point, E>
         where E: de::Error
     {
-        grammar::point(value).map_err(E::custom)
+        grammar::point(value).map_err(|err| grammar_error("point", value, err))
     }
 
     fn visit_map<M>(self, mut access: M) -> Result<Point, M::Error>
@@ -175,3 +202,21 @@ impl<'de> Deserialize<'de> for Point {
         deserializer.deserialize_struct("Point", POINT_FIELDS, PointVisitor)
     }
 }
+
+/// Reads a box from two opposite corners, in any order. Boxes without width
+/// or height are rejected, since nothing could be shown in them.
+impl<'de> Deserialize<'de> for BoundingBox {
+    fn deserialize<D>(deserializer: D) -> Result<BoundingBox, D::Error>
+        where D: Deserializer<'de>
+    {
+        let (corner, opposite) = <(Point, Point)>::deserialize(deserializer)?;
+        let bounding_box = BoundingBox::new(corner, opposite);
+
+        if !(bounding_box.width() > 0.0 && bounding_box.height() > 0.0) {
+            return Err(D::Error::custom(format_args!("box from `{}` to `{}` has no area",
+                corner, opposite)));
+        }
+
+        Ok(bounding_box)
+    }
+}
@@ -1,5 +1,12 @@
 mod serialize;
 pub mod deserialize;
+pub mod notation;
+#[cfg(test)]
+mod test_dir;
+
+pub use self::serialize::{to_pretty_string, reformat};
+#[cfg(test)]
+pub use self::test_dir::TestDir;
 
 pub trait Lerp {
     fn lerp(&self, target: &Self, t: f64) -> Self;
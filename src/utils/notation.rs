@@ -0,0 +1,88 @@
+use graphics::Point;
+
+/// Number of decimals written for coordinates and radii
+const PRECISION: usize = 3;
+
+/// Writes the point as `x | y`.
+pub fn cartesian(point: Point) -> String {
+    format!("{:.*} | {:.*}", PRECISION, point.x(), PRECISION, point.y())
+}
+
+/// Writes the point as `r; φ°`, with the angle between 0° and 360°.
+pub fn polar(point: Point) -> String {
+    let degrees = point.angle().to_degrees();
+    let degrees = if degrees < 0.0 { degrees + 360.0 } else { degrees };
+
+    format!("{:.*}; {:.1}°", PRECISION, point.length(), degrees)
+}
+
+/// Rounds points to the guides of the viewer.
+#[derive(Copy, Clone, PartialEq, Default, Debug)]
+pub enum Snap {
+    #[default]
+    None,
+    /// Round both coordinates to multiples of the spacing
+    Grid(f64),
+    /// Round the radius to multiples of the first step and the angle to
+    /// multiples of the second one, in degrees
+    Polar(f64, f64)
+}
+
+impl Snap {
+    pub fn apply(self, point: Point) -> Point {
+        let round = |value: f64, step: f64| (value / step).round() * step;
+
+        match self {
+            Snap::None => point,
+            Snap::Grid(spacing) => Point::new(round(point.x(), spacing), round(point.y(), spacing)),
+            Snap::Polar(radius_step, angle_step) => {
+                let radius = round(point.length(), radius_step);
+                let angle = round(point.angle().to_degrees(), angle_step);
+                Point::default().on_circle(radius, angle.to_radians())
+            }
+        }
+    }
+
+    /// Writes the snapped point in the notation matching the snapping.
+    pub fn format(self, point: Point) -> String {
+        let point = self.apply(point);
+
+        match self {
+            Snap::Polar(..) => polar(point),
+            _ => cartesian(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use graphics::Point;
+    use super::{cartesian, polar, Snap};
+
+    fn parse(notation: &str) -> Point {
+        serde_json::from_str(&format!("\"{}\"", notation)).unwrap()
+    }
+
+    #[test]
+    fn notations_can_be_parsed() {
+        let point = Point::new(-0.25, 0.4);
+
+        assert_eq!(cartesian(point), "-0.250 | 0.400");
+        assert!(parse(&cartesian(point)).distance(point) < 1e-9);
+
+        assert_eq!(polar(Point::new(0.0, -0.5)), "0.500; 270.0°");
+        assert!(parse(&polar(point)).distance(point) < 1e-3);
+    }
+
+    #[test]
+    fn snap_rounds_to_guides() {
+        let point = Point::new(0.31, -0.47);
+
+        assert_eq!(Snap::None.format(point), "0.310 | -0.470");
+        assert_eq!(Snap::Grid(0.1).format(point), "0.300 | -0.500");
+        // 0.56 away, at about -56.6°
+        assert_eq!(Snap::Polar(0.1, 15.0).format(point), "0.600; 300.0°");
+    }
+}
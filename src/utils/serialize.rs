@@ -1,6 +1,78 @@
 use serde::ser::{Serialize, Serializer};
+use serde_json::{self, Value};
 
-use graphics::Style;
+use graphics::{Style, Point, BoundingBox};
+
+const INDENT: &str = "    ";
+
+/// Writes the value as JSON laid out like the files: one field per line,
+/// indented by four spaces, with short arrays kept on one line and objects
+/// in arrays following each other as `}, {`.
+pub fn to_pretty_string<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    let mut output = String::new();
+    write_value(&mut output, &serde_json::to_value(value)?, 0);
+    output.push('\n');
+
+    Ok(output)
+}
+
+/// Lays out JSON source like the files, keeping the order of the fields and
+/// the notation of the values.
+pub fn reformat(source: &str) -> serde_json::Result<String> {
+    to_pretty_string(&serde_json::from_str::<Value>(source)?)
+}
+
+fn write_value(output: &mut String, value: &Value, depth: usize) {
+    match *value {
+        Value::Array(ref values) if values.iter().all(|value| value.is_object()) => {
+            output.push('[');
+
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+
+                write_value(output, value, depth);
+            }
+
+            output.push(']');
+        },
+        Value::Array(ref values) if values.iter().all(|value| !value.is_object() && !value.is_array()) => {
+            let values = values.iter().map(Value::to_string).collect::<Vec<_>>();
+            output.push_str(&format!("[{}]", values.join(", ")));
+        },
+        Value::Array(ref values) => {
+            output.push_str("[\n");
+
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(",\n");
+                }
+
+                output.push_str(&INDENT.repeat(depth + 1));
+                write_value(output, value, depth + 1);
+            }
+
+            output.push_str(&format!("\n{}]", INDENT.repeat(depth)));
+        },
+        Value::Object(ref fields) if fields.is_empty() => output.push_str("{}"),
+        Value::Object(ref fields) => {
+            output.push_str("{\n");
+
+            for (index, (key, value)) in fields.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(",\n");
+                }
+
+                output.push_str(&format!("{}{}: ", INDENT.repeat(depth + 1), Value::String(key.clone())));
+                write_value(output, value, depth + 1);
+            }
+
+            output.push_str(&format!("\n{}}}", INDENT.repeat(depth)));
+        },
+        ref value => output.push_str(&value.to_string())
+    }
+}
 
 impl Serialize for Style {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -12,3 +84,53 @@ impl Serialize for Style {
         })
     }
 }
+
+/// Writes the point in the cartesian notation of the files.
+impl Serialize for Point {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Writes the box as its two corners, as it is read.
+impl Serialize for BoundingBox {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        (self.min(), self.max()).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reformat;
+
+    #[test]
+    fn reformat_keeps_order_and_notation() {
+        let source = r#"{"groups": [{"segments": [{"type": "line", "start": "0 | 0", "end": "1; 45°"}]},
+            {"segments": [], "close": true}], "color": [0, 0.5, 1.0], "viewbox": ["-1 | -1", "1 | 1"]}"#;
+
+        assert_eq!(reformat(source).unwrap(), r#"{
+    "groups": [{
+        "segments": [{
+            "type": "line",
+            "start": "0 | 0",
+            "end": "1; 45°"
+        }]
+    }, {
+        "segments": [],
+        "close": true
+    }],
+    "color": [0, 0.5, 1.0],
+    "viewbox": ["-1 | -1", "1 | 1"]
+}
+"#);
+    }
+
+    #[test]
+    fn reformat_nested_arrays() {
+        assert_eq!(reformat("[[1, 2], [], {}]").unwrap(), "[\n    [1, 2],\n    [],\n    {}\n]\n");
+    }
+}
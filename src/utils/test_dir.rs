@@ -0,0 +1,30 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// An empty directory for a single test, which is removed again when dropped.
+/// Its name contains the process id, so that concurrent runs do not share it.
+pub struct TestDir {
+    path: PathBuf
+}
+
+impl TestDir {
+    pub fn new(test: &str) -> TestDir {
+        let path = env::temp_dir().join(format!("phint-{}-{}", process::id(), test));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        TestDir {path}
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,1198 @@
+use std::mem;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, BTreeMap};
+use std::rc::Rc;
+use cairo::{self, ImageSurface};
+use gdk;
+use gtk;
+use gtk::prelude::*;
+
+use phint;
+use phint::graphics::{Graphic, MorphGraphic, Subdivision, Point, BoundingBox, Affine, Editor};
+use phint::render::{self, CairoRenderer, Fit, View, Overlay, Sheet, render_image};
+use phint::utils::notation::{self, Snap};
+use phint::cli::{self, EXIT_SUCCESS, EXIT_FAILURE, EXIT_IO};
+
+/// Factor by which a step of the scroll wheel zooms
+const ZOOM_STEP: f64 = 1.2;
+/// Distance between the lines of the grid and between the circles of the polar overlay
+const GRID_SPACING: f64 = 0.1;
+/// Pixels the pointer may move between pressing and releasing, to still count as a click
+const CLICK_DISTANCE: f64 = 3.0;
+/// Pixels the pointer may be away from a handle, to still grab it
+const GRAB_DISTANCE: f64 = 6.0;
+/// Time between the frames of the onion skin
+const ONION_SPACING: f64 = 0.05;
+const FILMSTRIP_FRAMES: usize = 9;
+const FILMSTRIP_HEIGHT: i32 = 72;
+/// Size of the thumbnails in the gallery and the matrix
+const THUMBNAIL_SIZE: i32 = 96;
+/// Space for the names below the thumbnails of the gallery
+const LABEL_HEIGHT: i32 = 18;
+
+/// The graphics of a directory, along with their names and paths.
+#[derive(Default)]
+struct Collection {
+    /// The names prefixed with their categories, like `leaves/oak`
+    names: Vec<String>,
+    paths: Vec<PathBuf>,
+    graphics: Vec<Rc<Graphic>>,
+    /// The messages of the files which failed to load
+    errors: Vec<String>,
+    /// Editors of graphics with unsaved changes which are not edited right now
+    edits: HashMap<usize, Editor>
+}
+
+impl Collection {
+    fn load<P: AsRef<Path>>(path: P) -> io::Result<Collection> {
+        let mut collection = Collection::default();
+
+        for entry in phint::scan(path)? {
+            match entry.graphic {
+                Ok(graphic) => {
+                    collection.names.push(if entry.category.is_empty() {
+                        entry.name
+                    } else {
+                        format!("{}/{}", entry.category, entry.name)
+                    });
+                    collection.paths.push(entry.path);
+                    collection.graphics.push(Rc::new(graphic));
+                },
+                Err(err) => collection.errors.push(err.to_string())
+            }
+        }
+
+        Ok(collection)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|other| other == name)
+    }
+
+    /// Returns the indices of the graphics with `query` in their name, ignoring case.
+    fn search(&self, query: &str) -> Vec<usize> {
+        let query = query.to_lowercase();
+
+        self.names.iter().enumerate()
+            .filter(|&(_, name)| name.to_lowercase().contains(&query))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+/// Chooses a graphic from a menu with a submenu for every category.
+#[derive(Clone)]
+struct Chooser {
+    combo: gtk::ComboBox,
+    store: gtk::TreeStore,
+    /// The row of every graphic, by index in the collection
+    rows: Rc<RefCell<Vec<gtk::TreeIter>>>
+}
+
+impl Chooser {
+    fn new(collection: &Collection) -> Chooser {
+        // the label of a row and the index of its graphic, or -1 for a category
+        let store = gtk::TreeStore::new(&[gtk::Type::String, gtk::Type::I32]);
+        let combo = gtk::ComboBox::new_with_model(&store);
+        let cell = gtk::CellRendererText::new();
+        combo.pack_start(&cell, true);
+        combo.add_attribute(&cell, "text", 0);
+
+        let chooser = Chooser {combo, store, rows: Rc::new(RefCell::new(Vec::new()))};
+        chooser.fill(collection);
+        chooser
+    }
+
+    /// Replaces the rows with the graphics of `collection`, which leaves nothing chosen.
+    fn fill(&self, collection: &Collection) {
+        let mut categories = HashMap::<String, gtk::TreeIter>::new();
+        let mut rows = self.rows.borrow_mut();
+        self.store.clear();
+        rows.clear();
+
+        for (index, name) in collection.names.iter().enumerate() {
+            let mut parent = None;
+            let mut category = String::new();
+            let mut parts = name.split('/').collect::<Vec<_>>();
+            let label = parts.pop().unwrap_or("");
+
+            for part in parts {
+                if !category.is_empty() {
+                    category.push('/');
+                }
+                category.push_str(part);
+
+                let row = categories.entry(category.clone()).or_insert_with(|| {
+                    self.store.insert_with_values(parent.as_ref(), None, &[0, 1], &[&part, &-1])
+                }).clone();
+                parent = Some(row);
+            }
+
+            rows.push(self.store.insert_with_values(parent.as_ref(), None, &[0, 1], &[&label, &(index as i32)]));
+        }
+    }
+
+    /// Returns the index of the chosen graphic, or none if nothing or a category is chosen.
+    fn active_index(&self) -> Option<usize> {
+        self.combo.get_active_iter()
+            .and_then(|row| self.store.get_value(&row, 1).get::<i32>())
+            .and_then(|index| if index < 0 { None } else { Some(index as usize) })
+    }
+
+    fn set_active(&self, index: i32) {
+        let rows = self.rows.borrow();
+        self.combo.set_active_iter(if index < 0 { None } else { rows.get(index as usize) });
+    }
+
+    fn connect_changed<F: Fn(&Chooser) + 'static>(&self, f: F) {
+        let chooser = self.clone();
+        self.combo.connect_changed(move |_| f(&chooser));
+    }
+}
+
+/// Shows the messages above everything else, or hides the label if there are none.
+/// Saves which failed come first, they stay until the file is saved again.
+fn show_problems(label: &gtk::Label, save_errors: &BTreeMap<PathBuf, String>, problems: &[String]) {
+    let problems = save_errors.values().chain(problems).cloned().collect::<Vec<_>>();
+    label.set_text(&problems.join("\n"));
+    label.set_visible(!problems.is_empty());
+}
+
+/// Edits the graphic at `index` if `active`, or stops editing otherwise. An
+/// editor with unsaved changes is kept with the collection, so that they are
+/// still saved against the file when the graphic is edited again.
+fn start_editing(editor: &RefCell<Option<(usize, Editor)>>, collection: &mut Collection, index: usize, active: bool) {
+    if let Some((previous, previous_editor)) = editor.borrow_mut().take() {
+        if previous_editor.is_modified() {
+            collection.edits.insert(previous, previous_editor);
+        }
+    }
+
+    if active {
+        let resumed = collection.edits.remove(&index)
+            .unwrap_or_else(|| Editor::new((*collection.graphics[index]).clone()));
+        *editor.borrow_mut() = Some((index, resumed));
+    }
+}
+
+/// Rendered thumbnails of the graphics and of the middle of morphs between
+/// them, which are kept until the graphics or the subdivision change.
+struct Thumbnails {
+    graphics: HashMap<usize, ImageSurface>,
+    morphs: HashMap<(usize, usize), ImageSurface>,
+    subdivision: Subdivision
+}
+
+impl Thumbnails {
+    fn new() -> Thumbnails {
+        Thumbnails {
+            graphics: HashMap::new(),
+            morphs: HashMap::new(),
+            subdivision: Subdivision::default()
+        }
+    }
+
+    fn graphic(&mut self, graphics: &[Rc<Graphic>], index: usize) -> &ImageSurface {
+        self.graphics.entry(index).or_insert_with(|| {
+            let graphic = &graphics[index];
+            let frame = Fit::Viewbox.graphic_frame(graphic);
+            render_image(THUMBNAIL_SIZE, THUMBNAIL_SIZE, &frame, |renderer| graphic.draw(renderer))
+        })
+    }
+
+    fn morph(&mut self, graphics: &[Rc<Graphic>], start: usize, target: usize) -> &ImageSurface {
+        self.morphs.entry((start, target)).or_insert_with(|| {
+            let (morph, groups) = MorphGraphic::with_subdivision(graphics[start].clone(),
+                graphics[target].clone(), self.subdivision);
+            let frame = Fit::Viewbox.morph_frame(&morph);
+            render_image(THUMBNAIL_SIZE, THUMBNAIL_SIZE, &frame, |renderer| morph.draw(renderer, groups.link(&morph), 0.5))
+        })
+    }
+
+    /// Drops every thumbnail which shows the graphic at `index`.
+    fn forget(&mut self, index: usize) {
+        self.graphics.remove(&index);
+        self.morphs.retain(|&(start, target), _| start != index && target != index);
+    }
+
+    /// Drops every thumbnail, after the graphics were loaded anew.
+    fn clear(&mut self) {
+        self.graphics.clear();
+        self.morphs.clear();
+    }
+
+    /// Subdivides the morphs with `subdivision` from now on, dropping those
+    /// which were subdivided differently.
+    fn set_subdivision(&mut self, subdivision: Subdivision) {
+        if subdivision != self.subdivision {
+            self.subdivision = subdivision;
+            self.morphs.clear();
+        }
+    }
+}
+
+/// Paints the image with its top left corner at `corner`.
+fn paint_image(ctx: &cairo::Context, image: &ImageSurface, corner: Point) {
+    ctx.set_source_surface(image, corner.x(), corner.y());
+    ctx.rectangle(corner.x(), corner.y(), image.get_width() as f64, image.get_height() as f64);
+    ctx.fill();
+}
+
+fn gallery_sheet(width: i32) -> Sheet {
+    Sheet::fitting(width as f64, THUMBNAIL_SIZE as f64, (THUMBNAIL_SIZE + LABEL_HEIGHT) as f64)
+}
+
+/// Returns the sheet of the matrix for `count` graphics, with a row and a
+/// column for the graphics themselves before the morphs between them.
+fn matrix_sheet(count: usize) -> Sheet {
+    Sheet::new(count + 1, THUMBNAIL_SIZE as f64, THUMBNAIL_SIZE as f64)
+}
+
+fn chosen_subdivision(subdivision_chooser: &gtk::ComboBoxText) -> Subdivision {
+    match subdivision_chooser.get_active() {
+        1 => Subdivision::Proportional(0),
+        _ => Subdivision::LeastCommonMultiple
+    }
+}
+
+fn chosen_fit(fit_chooser: &gtk::ComboBoxText) -> Fit {
+    match fit_chooser.get_active() {
+        1 => Fit::Bounds,
+        _ => Fit::Viewbox
+    }
+}
+
+/// Opens the viewer and returns the exit code once it is closed.
+pub fn view(options: &cli::View) -> i32 {
+    let path = options.directory.as_str();
+
+    let collection = match Collection::load(path) {
+        Ok(collection) => collection,
+        Err(err) => {
+            eprintln!("error: {}: {}", path, err);
+            return EXIT_IO;
+        }
+    };
+
+    // graphics chosen by name start out selected, otherwise the first one
+    let chosen = {
+        let position = |name: &Option<String>| match *name {
+            Some(ref name) => collection.position(name)
+                .ok_or_else(|| format!("error: no graphic named {} in {}", name, path)),
+            None => Ok(0)
+        };
+
+        position(&options.start).and_then(|start| position(&options.target).map(|target| (start, target)))
+    };
+
+    let (start_index, target_index) = match chosen {
+        Ok(chosen) => chosen,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_FAILURE;
+        }
+    };
+
+    if gtk::init().is_err() {
+        eprintln!("Failed to initialize GTK.");
+        return EXIT_FAILURE;
+    }
+
+    let window = gtk::Window::new(gtk::WindowType::Toplevel);
+    window.set_title("phint");
+    window.set_icon_name("applications-graphics");
+
+    window.connect_delete_event(|_, _| {
+        gtk::main_quit();
+        Inhibit(false)
+    });
+
+    // there is nothing to choose from, so show a hint instead of the viewer
+    if collection.graphics.is_empty() {
+        let placeholder = gtk::Label::new(Some(format!("No graphics found in {}", path).as_str()));
+        window.add(&placeholder);
+        window.show_all();
+        window.maximize();
+        gtk::main();
+        return EXIT_SUCCESS;
+    }
+
+    let root = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    window.add(&root);
+
+    // files which failed to load or save
+    let problems = gtk::Label::new(None);
+    problems.set_halign(gtk::Align::Start);
+    problems.set_selectable(true);
+    problems.set_no_show_all(true);
+    root.pack_start(&problems, false, false, 0);
+    let save_errors = Rc::new(RefCell::new(BTreeMap::new()));
+    show_problems(&problems, &save_errors.borrow(), &collection.errors);
+
+    let notebook = gtk::Notebook::new();
+    root.pack_start(&notebook, true, true, 0);
+
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    notebook.append_page(&container, Some(&gtk::Label::new(Some("morph"))));
+
+    let bar = gtk::Toolbar::new();
+    container.pack_start(&bar, false, false, 0);
+
+    let start_chooser = Chooser::new(&collection);
+    let item = gtk::ToolItem::new();
+    bar.insert(&item, -1);
+    item.add(&start_chooser.combo);
+
+    let scale = gtk::Scale::new_with_range(gtk::Orientation::Horizontal, 0.0, 1.0, 0.001);
+    let item = gtk::ToolItem::new();
+    bar.insert(&item, -1);
+    bar.set_item_expand(&item, true);
+    item.add(&scale);
+
+    if let Some(t) = options.t {
+        scale.set_value(t);
+    }
+
+    let target_chooser = Chooser::new(&collection);
+    let item = gtk::ToolItem::new();
+    bar.insert(&item, -1);
+    item.add(&target_chooser.combo);
+
+    let subdivision_chooser = gtk::ComboBoxText::new();
+    let item = gtk::ToolItem::new();
+    bar.insert(&item, -1);
+    item.add(&subdivision_chooser);
+
+    subdivision_chooser.append_text("least common multiple");
+    subdivision_chooser.append_text("proportional");
+
+    let fit_chooser = gtk::ComboBoxText::new();
+    let item = gtk::ToolItem::new();
+    bar.insert(&item, -1);
+    item.add(&fit_chooser);
+
+    fit_chooser.append_text("fit viewbox");
+    fit_chooser.append_text("fit bounds");
+
+    let grid_toggle = gtk::ToggleButton::new_with_label("grid");
+    let polar_toggle = gtk::ToggleButton::new_with_label("polar");
+    let frame_toggle = gtk::ToggleButton::new_with_label("frame");
+    // shows the beziers the graphics are made of, on top of them
+    let debug_toggle = gtk::ToggleButton::new_with_label("debug");
+    // shows which beziers of start and target were paired up
+    let pairs_toggle = gtk::ToggleButton::new_with_label("pairs");
+    // drags the handles of the start graphic around
+    let edit_toggle = gtk::ToggleButton::new_with_label("edit");
+    // shows the frames around the current one
+    let onion_toggle = gtk::ToggleButton::new_with_label("onion");
+
+    let toggles = [&grid_toggle, &polar_toggle, &frame_toggle, &debug_toggle, &pairs_toggle, &edit_toggle, &onion_toggle];
+
+    for toggle in &toggles {
+        let item = gtk::ToolItem::new();
+        bar.insert(&item, -1);
+        item.add(*toggle);
+    }
+
+    // frames of the onion skin on either side
+    let onion_count = gtk::SpinButton::new_with_range(1.0, 10.0, 1.0);
+    onion_count.set_value(3.0);
+    let item = gtk::ToolItem::new();
+    bar.insert(&item, -1);
+    item.add(&onion_count);
+
+    // degrees between the spokes of the polar overlay
+    let angle_step = gtk::SpinButton::new_with_range(1.0, 90.0, 1.0);
+    angle_step.set_value(15.0);
+    let item = gtk::ToolItem::new();
+    bar.insert(&item, -1);
+    item.add(&angle_step);
+
+    let snap_chooser = gtk::ComboBoxText::new();
+    let item = gtk::ToolItem::new();
+    bar.insert(&item, -1);
+    item.add(&snap_chooser);
+
+    snap_chooser.append_text("no snapping");
+    snap_chooser.append_text("snap to grid");
+    snap_chooser.append_text("snap to angles");
+    snap_chooser.set_active(0);
+
+    let needs_change = Rc::new(Cell::new(false));
+    // edits and reloads replace the graphics, which the morph picks up on its next change
+    let collection = Rc::new(RefCell::new(collection));
+    let thumbnails = Rc::new(RefCell::new(Thumbnails::new()));
+    // set while the choosers are filled anew, which changes their selection on the way
+    let reloading = Rc::new(Cell::new(false));
+    let editor = Rc::new(RefCell::new(None));
+    let grabbed = Rc::new(Cell::new(None));
+    let canvas = gtk::DrawingArea::new();
+    container.pack_start(&canvas, true, true, 0);
+
+    // thumbnails of evenly spaced frames, which jump there when clicked
+    let filmstrip = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+    container.pack_start(&filmstrip, false, false, 0);
+
+    let readout = gtk::Label::new(None);
+    readout.set_halign(gtk::Align::Start);
+    container.pack_start(&readout, false, false, 0);
+
+    start_chooser.connect_changed({
+        let canvas = canvas.clone();
+        let needs_change = needs_change.clone();
+        let collection = collection.clone();
+        let editor = editor.clone();
+        let edit_toggle = edit_toggle.clone();
+        let reloading = reloading.clone();
+        move |start_chooser| {
+            // categories have no graphic to edit
+            let index = match start_chooser.active_index() {
+                Some(index) if !reloading.get() => index,
+                _ => return
+            };
+
+            start_editing(&editor, &mut collection.borrow_mut(), index, edit_toggle.get_active());
+            needs_change.set(true);
+            canvas.queue_draw();
+        }
+    });
+    start_chooser.set_active(start_index as i32);
+
+    scale.connect_value_changed({
+        let canvas = canvas.clone();
+        let filmstrip = filmstrip.clone();
+        move |_| {
+            canvas.queue_draw();
+            filmstrip.queue_draw();
+        }
+    });
+
+    target_chooser.connect_changed({
+        let canvas = canvas.clone();
+        let needs_change = needs_change.clone();
+        move |_| {
+            needs_change.set(true);
+            canvas.queue_draw();
+        }
+    });
+    target_chooser.set_active(target_index as i32);
+
+    subdivision_chooser.connect_changed({
+        let canvas = canvas.clone();
+        let needs_change = needs_change.clone();
+        move |_| {
+            needs_change.set(true);
+            canvas.queue_draw();
+        }
+    });
+    subdivision_chooser.set_active(0);
+
+    fit_chooser.connect_changed({
+        let canvas = canvas.clone();
+        let filmstrip = filmstrip.clone();
+        move |_| {
+            canvas.queue_draw();
+            filmstrip.queue_draw();
+        }
+    });
+    fit_chooser.set_active(0);
+
+    for toggle in &toggles {
+        toggle.connect_toggled({
+            let canvas = canvas.clone();
+            move |_| {
+                canvas.queue_draw();
+            }
+        });
+    }
+
+    edit_toggle.connect_toggled({
+        let canvas = canvas.clone();
+        let collection = collection.clone();
+        let editor = editor.clone();
+        let start_chooser = start_chooser.clone();
+        move |edit_toggle| {
+            if let Some(index) = start_chooser.active_index() {
+                start_editing(&editor, &mut collection.borrow_mut(), index, edit_toggle.get_active());
+                canvas.queue_draw();
+            }
+        }
+    });
+
+    for spin in &[&angle_step, &onion_count] {
+        spin.connect_value_changed({
+            let canvas = canvas.clone();
+            move |_| {
+                canvas.queue_draw();
+            }
+        });
+    }
+
+    let view = Rc::new(RefCell::new(View::default()));
+    // the transform of the last drawing, to find the point under the pointer
+    let transform = Rc::new(Cell::new(Affine::identity()));
+    // the pointer position while panning
+    let drag = Rc::new(Cell::new(None));
+    // where the first button went down, to tell clicks from drags
+    let press = Rc::new(Cell::new(None));
+
+    canvas.add_events((gdk::SCROLL_MASK | gdk::BUTTON_PRESS_MASK
+        | gdk::BUTTON_RELEASE_MASK | gdk::POINTER_MOTION_MASK).bits() as i32);
+
+    canvas.connect_scroll_event({
+        let view = view.clone();
+        move |canvas, event| {
+            let factor = match event.get_direction() {
+                gdk::ScrollDirection::Up => ZOOM_STEP,
+                gdk::ScrollDirection::Down => 1.0 / ZOOM_STEP,
+                _ => return Inhibit(false)
+            };
+
+            view.borrow_mut().zoom_at(factor, Point::from(event.get_position()));
+            canvas.queue_draw();
+            Inhibit(true)
+        }
+    });
+
+    canvas.connect_button_press_event({
+        let drag = drag.clone();
+        let press = press.clone();
+        let transform = transform.clone();
+        let editor = editor.clone();
+        let grabbed = grabbed.clone();
+        move |_, event| {
+            if event.get_button() != 1 {
+                return Inhibit(false);
+            }
+
+            let position = Point::from(event.get_position());
+            let affine = transform.get();
+
+            // grab a handle if there is one under the pointer, otherwise pan
+            let handle = match (&*editor.borrow(), affine.invert()) {
+                (&Some((_, ref editor)), Some(inverse)) =>
+                    editor.handle_at(inverse.apply(position), GRAB_DISTANCE / affine.scale_factor()),
+                _ => None
+            };
+
+            if handle.is_some() {
+                grabbed.set(handle);
+            } else {
+                drag.set(Some(position));
+                press.set(Some(position));
+            }
+
+            Inhibit(false)
+        }
+    });
+
+    canvas.connect_button_release_event({
+        let drag = drag.clone();
+        let transform = transform.clone();
+        let snap_chooser = snap_chooser.clone();
+        let angle_step = angle_step.clone();
+        let canvas = canvas.clone();
+        let needs_change = needs_change.clone();
+        let collection = collection.clone();
+        let editor = editor.clone();
+        let grabbed = grabbed.clone();
+        let thumbnails = thumbnails.clone();
+        move |_, event| {
+            if event.get_button() != 1 {
+                return Inhibit(false);
+            }
+
+            if grabbed.get().is_some() {
+                grabbed.set(None);
+
+                if let Some((index, ref mut editor)) = *editor.borrow_mut() {
+                    editor.release();
+                    collection.borrow_mut().graphics[index] = Rc::new(editor.graphic().clone());
+                    thumbnails.borrow_mut().forget(index);
+                    needs_change.set(true);
+                    canvas.queue_draw();
+                }
+
+                return Inhibit(false);
+            }
+
+            let position = Point::from(event.get_position());
+            drag.set(None);
+
+            // copy the point under the pointer, unless the view was panned
+            let clicked = press.get().map_or(false, |press| press.distance(position) < CLICK_DISTANCE);
+
+            if let (true, Some(inverse)) = (clicked, transform.get().invert()) {
+                let snap = match snap_chooser.get_active() {
+                    1 => Snap::Grid(GRID_SPACING),
+                    2 => Snap::Polar(GRID_SPACING, angle_step.get_value()),
+                    _ => Snap::None
+                };
+
+                let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+                clipboard.set_text(&snap.format(inverse.apply(position)));
+            }
+
+            press.set(None);
+            Inhibit(false)
+        }
+    });
+
+    canvas.connect_motion_notify_event({
+        let view = view.clone();
+        let transform = transform.clone();
+        let editor = editor.clone();
+        let grabbed = grabbed.clone();
+        move |canvas, event| {
+            let position = Point::from(event.get_position());
+
+            if let (Some(handle), Some(inverse)) = (grabbed.get(), transform.get().invert()) {
+                if let Some((_, ref mut editor)) = *editor.borrow_mut() {
+                    editor.drag(handle, inverse.apply(position));
+                    canvas.queue_draw();
+                }
+            }
+
+            if let Some(last) = drag.get() {
+                view.borrow_mut().pan(position - last);
+                drag.set(Some(position));
+                canvas.queue_draw();
+            }
+
+            if let Some(inverse) = transform.get().invert() {
+                let point = inverse.apply(position);
+                readout.set_text(&format!("{}    {}", notation::cartesian(point), notation::polar(point)));
+            }
+
+            Inhibit(false)
+        }
+    });
+
+    window.connect_key_press_event({
+        let canvas = canvas.clone();
+        let view = view.clone();
+        let needs_change = needs_change.clone();
+        let collection = collection.clone();
+        let editor = editor.clone();
+        let thumbnails = thumbnails.clone();
+        let problems = problems.clone();
+        let save_errors = save_errors.clone();
+        move |_, event| {
+            let key = event.get_keyval();
+
+            if key == gdk::enums::key::Home {
+                view.borrow_mut().reset();
+                canvas.queue_draw();
+                return Inhibit(true);
+            }
+
+            if !event.get_state().contains(gdk::CONTROL_MASK) {
+                return Inhibit(false);
+            }
+
+            if let Some((index, ref mut editor)) = *editor.borrow_mut() {
+                // shift turns z into Z, which redoes like in most editors
+                let changed = match key {
+                    gdk::enums::key::z => editor.undo(),
+                    gdk::enums::key::Z | gdk::enums::key::y => editor.redo(),
+                    gdk::enums::key::s => {
+                        let path = collection.borrow().paths[index].clone();
+
+                        match editor.save(&path) {
+                            Ok(()) => save_errors.borrow_mut().remove(&path),
+                            Err(err) => save_errors.borrow_mut().insert(path.clone(),
+                                format!("error while saving {}: {}", path.to_string_lossy(), err))
+                        };
+
+                        show_problems(&problems, &save_errors.borrow(), &collection.borrow().errors);
+
+                        false
+                    },
+                    _ => return Inhibit(false)
+                };
+
+                if changed {
+                    collection.borrow_mut().graphics[index] = Rc::new(editor.graphic().clone());
+                    thumbnails.borrow_mut().forget(index);
+                    needs_change.set(true);
+                    canvas.queue_draw();
+                }
+
+                return Inhibit(true);
+            }
+
+            Inhibit(false)
+        }
+    });
+
+    // narrows down the graphics in the gallery and the matrix by name
+    let search = gtk::SearchEntry::new();
+    notebook.set_action_widget(&search, gtk::PackType::End);
+    let shown = Rc::new(RefCell::new(collection.borrow().search("")));
+
+    let gallery = gtk::DrawingArea::new();
+    gallery.add_events(gdk::BUTTON_PRESS_MASK.bits() as i32);
+    let scrolled = gtk::ScrolledWindow::new(None, None);
+    scrolled.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+    scrolled.add(&gallery);
+    notebook.append_page(&scrolled, Some(&gtk::Label::new(Some("gallery"))));
+
+    gallery.connect_draw({
+        let collection = collection.clone();
+        let thumbnails = thumbnails.clone();
+        let shown = shown.clone();
+        let start_chooser = start_chooser.clone();
+        let target_chooser = target_chooser.clone();
+        move |gallery, ctx| {
+            let shown = shown.borrow();
+            let sheet = gallery_sheet(gallery.get_allocated_width());
+            let height = sheet.height(shown.len()) as i32;
+
+            if gallery.get_size_request().1 != height {
+                gallery.set_size_request(-1, height);
+            }
+
+            ctx.set_source_rgb(1.0, 1.0, 1.0);
+            ctx.paint();
+
+            let collection = collection.borrow();
+            let mut thumbnails = thumbnails.borrow_mut();
+            let (_, top, _, bottom) = ctx.clip_extents();
+
+            for position in sheet.visible(top, bottom, shown.len()) {
+                let index = shown[position];
+                let cell = sheet.cell(position);
+                paint_image(ctx, thumbnails.graphic(&collection.graphics, index), cell.min());
+
+                // frame the chosen start in blue and the target in red
+                let chosen = if Some(index) == start_chooser.active_index() {
+                    Some((0.2, 0.4, 0.9))
+                } else if Some(index) == target_chooser.active_index() {
+                    Some((0.9, 0.3, 0.2))
+                } else {
+                    None
+                };
+
+                if let Some((red, green, blue)) = chosen {
+                    ctx.set_source_rgb(red, green, blue);
+                    ctx.set_line_width(2.0);
+                    ctx.rectangle(cell.min().x() + 1.0, cell.min().y() + 1.0, THUMBNAIL_SIZE as f64 - 2.0, THUMBNAIL_SIZE as f64 - 2.0);
+                    ctx.stroke();
+                }
+
+                // keep long names within their cell
+                ctx.save();
+                ctx.rectangle(cell.min().x(), cell.min().y(), cell.width(), cell.height());
+                ctx.clip();
+                ctx.set_source_rgb(0.2, 0.2, 0.2);
+                ctx.move_to(cell.min().x() + 4.0, cell.max().y() - 5.0);
+                ctx.show_text(&collection.names[index]);
+                ctx.restore();
+            }
+
+            Inhibit(false)
+        }
+    });
+
+    // choose the start with the left button and the target with the right one
+    gallery.connect_button_press_event({
+        let shown = shown.clone();
+        let start_chooser = start_chooser.clone();
+        let target_chooser = target_chooser.clone();
+        move |gallery, event| {
+            let shown = shown.borrow();
+            let sheet = gallery_sheet(gallery.get_allocated_width());
+
+            if let Some(position) = sheet.index_at(Point::from(event.get_position()), shown.len()) {
+                match event.get_button() {
+                    1 => start_chooser.set_active(shown[position] as i32),
+                    3 => target_chooser.set_active(shown[position] as i32),
+                    _ => return Inhibit(false)
+                }
+
+                gallery.queue_draw();
+            }
+
+            Inhibit(false)
+        }
+    });
+
+    let matrix = gtk::DrawingArea::new();
+    matrix.add_events((gdk::BUTTON_PRESS_MASK | gdk::POINTER_MOTION_MASK).bits() as i32);
+    let scrolled = gtk::ScrolledWindow::new(None, None);
+    scrolled.add(&matrix);
+
+    // names the pair under the pointer
+    let pair_readout = gtk::Label::new(None);
+    pair_readout.set_halign(gtk::Align::Start);
+
+    let page = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    page.pack_start(&scrolled, true, true, 0);
+    page.pack_start(&pair_readout, false, false, 0);
+    notebook.append_page(&page, Some(&gtk::Label::new(Some("matrix"))));
+
+    matrix.connect_draw({
+        let collection = collection.clone();
+        let thumbnails = thumbnails.clone();
+        let shown = shown.clone();
+        let subdivision_chooser = subdivision_chooser.clone();
+        move |matrix, ctx| {
+            let shown = shown.borrow();
+            let sheet = matrix_sheet(shown.len());
+            let count = sheet.columns() * sheet.columns();
+            let (width, height) = (sheet.width(), sheet.height(count));
+
+            if matrix.get_size_request() != (width as i32, height as i32) {
+                matrix.set_size_request(width as i32, height as i32);
+            }
+
+            ctx.set_source_rgb(1.0, 1.0, 1.0);
+            ctx.paint();
+
+            let collection = collection.borrow();
+            let graphics = &collection.graphics;
+            let mut thumbnails = thumbnails.borrow_mut();
+            thumbnails.set_subdivision(chosen_subdivision(&subdivision_chooser));
+            let (left, top, right, bottom) = ctx.clip_extents();
+
+            for position in sheet.visible(top, bottom, count) {
+                let cell = sheet.cell(position);
+
+                if cell.max().x() < left || cell.min().x() > right {
+                    continue;
+                }
+
+                let image = match sheet.locate(position) {
+                    (0, 0) => continue,
+                    (0, column) => thumbnails.graphic(graphics, shown[column - 1]),
+                    (row, 0) => thumbnails.graphic(graphics, shown[row - 1]),
+                    (row, column) => thumbnails.morph(graphics, shown[row - 1], shown[column - 1])
+                };
+
+                paint_image(ctx, image, cell.min());
+            }
+
+            // separate the starts on the left and the targets on top from the morphs
+            let size = THUMBNAIL_SIZE as f64;
+            ctx.set_source_rgb(0.6, 0.6, 0.6);
+            ctx.set_line_width(1.0);
+            ctx.move_to(size + 0.5, 0.0);
+            ctx.line_to(size + 0.5, height);
+            ctx.move_to(0.0, size + 0.5);
+            ctx.line_to(width, size + 0.5);
+            ctx.stroke();
+
+            Inhibit(false)
+        }
+    });
+
+    matrix.connect_motion_notify_event({
+        let collection = collection.clone();
+        let shown = shown.clone();
+        move |_, event| {
+            let collection = collection.borrow();
+            let names = &collection.names;
+            let shown = shown.borrow();
+            let sheet = matrix_sheet(shown.len());
+            let count = sheet.columns() * sheet.columns();
+
+            let text = match sheet.index_at(Point::from(event.get_position()), count).map(|index| sheet.locate(index)) {
+                Some((0, 0)) | None => String::new(),
+                Some((0, column)) => format!("target {}", names[shown[column - 1]]),
+                Some((row, 0)) => format!("start {}", names[shown[row - 1]]),
+                Some((row, column)) => format!("{} to {}", names[shown[row - 1]], names[shown[column - 1]])
+            };
+
+            pair_readout.set_text(&text);
+            Inhibit(false)
+        }
+    });
+
+    // open the morph of a pair halfway through
+    matrix.connect_button_press_event({
+        let shown = shown.clone();
+        let notebook = notebook.clone();
+        let start_chooser = start_chooser.clone();
+        let target_chooser = target_chooser.clone();
+        let scale = scale.clone();
+        move |_, event| {
+            let shown = shown.borrow();
+            let sheet = matrix_sheet(shown.len());
+            let count = sheet.columns() * sheet.columns();
+
+            if let Some(index) = sheet.index_at(Point::from(event.get_position()), count) {
+                let (row, column) = sheet.locate(index);
+
+                if row > 0 && column > 0 {
+                    start_chooser.set_active(shown[row - 1] as i32);
+                    target_chooser.set_active(shown[column - 1] as i32);
+                    scale.set_value(0.5);
+                    notebook.set_current_page(Some(0));
+                }
+            }
+
+            Inhibit(false)
+        }
+    });
+
+    search.connect_changed({
+        let collection = collection.clone();
+        let shown = shown.clone();
+        let gallery = gallery.clone();
+        let matrix = matrix.clone();
+        move |search| {
+            *shown.borrow_mut() = collection.borrow().search(&search.get_text().unwrap_or_else(String::new));
+            gallery.queue_draw();
+            matrix.queue_draw();
+        }
+    });
+
+    let start = collection.borrow().graphics[start_index].clone();
+    let target = collection.borrow().graphics[target_index].clone();
+    let morph_data = Rc::new(RefCell::new(MorphGraphic::new(start, target)));
+
+    for index in 0..FILMSTRIP_FRAMES {
+        let t = index as f64 / (FILMSTRIP_FRAMES - 1) as f64;
+        let thumbnail = gtk::DrawingArea::new();
+        thumbnail.set_size_request(-1, FILMSTRIP_HEIGHT);
+        thumbnail.add_events(gdk::BUTTON_PRESS_MASK.bits() as i32);
+        filmstrip.pack_start(&thumbnail, true, true, 0);
+
+        thumbnail.connect_button_press_event({
+            let scale = scale.clone();
+            move |_, _| {
+                scale.set_value(t);
+                Inhibit(true)
+            }
+        });
+
+        thumbnail.connect_draw({
+            let morph_data = morph_data.clone();
+            let fit_chooser = fit_chooser.clone();
+            let scale = scale.clone();
+            move |thumbnail, ctx| {
+                let gtk::Allocation {width, height, ..} = thumbnail.get_allocation();
+                let (ref morph, ref groups) = *morph_data.borrow();
+
+                // highlight the frame closest to the current time
+                if (scale.get_value() - t).abs() < 0.5 / (FILMSTRIP_FRAMES - 1) as f64 {
+                    ctx.set_source_rgb(0.85, 0.9, 1.0);
+                } else {
+                    ctx.set_source_rgb(1.0, 1.0, 1.0);
+                }
+                ctx.paint();
+
+                let frame = chosen_fit(&fit_chooser).morph_frame(morph);
+                let mut renderer = CairoRenderer::new(ctx);
+                renderer.transform(&View::default().transform(width as f64, height as f64, &frame));
+                morph.draw_frame(&mut renderer, groups, t);
+
+                Inhibit(false)
+            }
+        });
+    }
+
+    // picks up files which were changed, added or removed in the meantime
+    if let Ok(watcher) = phint::Watcher::new(path) {
+        let watcher = RefCell::new(watcher);
+        let path = path.to_string();
+        let collection = collection.clone();
+        let thumbnails = thumbnails.clone();
+        let editor = editor.clone();
+        let reloading = reloading.clone();
+        let needs_change = needs_change.clone();
+        let start_chooser = start_chooser.clone();
+        let target_chooser = target_chooser.clone();
+        let edit_toggle = edit_toggle.clone();
+        let problems = problems.clone();
+        let save_errors = save_errors.clone();
+        let search = search.clone();
+        let shown = shown.clone();
+        let canvas = canvas.clone();
+        let filmstrip = filmstrip.clone();
+        let gallery = gallery.clone();
+        let matrix = matrix.clone();
+
+        gtk::timeout_add_seconds(1, move || {
+            match watcher.borrow_mut().poll() {
+                Ok(true) => {},
+                Ok(false) => return gtk::Continue(true),
+                Err(err) => {
+                    show_problems(&problems, &save_errors.borrow(), &[format!("error while reading {}: {}", path, err)]);
+                    return gtk::Continue(true);
+                }
+            }
+
+            let reloaded = match Collection::load(&path) {
+                Ok(reloaded) => reloaded,
+                Err(err) => {
+                    show_problems(&problems, &save_errors.borrow(), &[format!("error while reading {}: {}", path, err)]);
+                    return gtk::Continue(true);
+                }
+            };
+            show_problems(&problems, &save_errors.borrow(), &reloaded.errors);
+
+            // keep showing what was there before rather than nothing at all
+            if reloaded.graphics.is_empty() {
+                return gtk::Continue(true);
+            }
+
+            let (start, target) = {
+                let collection = collection.borrow();
+                let chosen = |chooser: &Chooser| chooser.active_index()
+                    .map(|index| (collection.names[index].clone(), collection.graphics[index].clone()));
+                (chosen(&start_chooser), chosen(&target_chooser))
+            };
+
+            // unsaved edits survive as long as their files did not change underneath them
+            let mut reloaded = reloaded;
+            let mut edits = mem::replace(&mut collection.borrow_mut().edits, HashMap::new());
+
+            if let Some((index, editor)) = editor.borrow_mut().take() {
+                edits.insert(index, editor);
+            }
+
+            for (index, editor) in edits {
+                let moved = reloaded.position(&collection.borrow().names[index]);
+
+                if let Some(moved) = moved {
+                    if *reloaded.graphics[moved] == *editor.saved() {
+                        reloaded.graphics[moved] = Rc::new(editor.graphic().clone());
+                        reloaded.edits.insert(moved, editor);
+                    }
+                }
+            }
+
+            *collection.borrow_mut() = reloaded;
+            thumbnails.borrow_mut().clear();
+
+            // fill the choosers anew and select the same graphics by name if they are still there
+            let position = |chosen: &Option<(String, Rc<Graphic>)>| chosen.as_ref()
+                .and_then(|&(ref name, _)| collection.borrow().position(name))
+                .unwrap_or(0);
+            let start_index = position(&start);
+            let target_index = position(&target);
+
+            reloading.set(true);
+            start_chooser.fill(&collection.borrow());
+            target_chooser.fill(&collection.borrow());
+            start_chooser.set_active(start_index as i32);
+            target_chooser.set_active(target_index as i32);
+            reloading.set(false);
+
+            start_editing(&editor, &mut collection.borrow_mut(), start_index, edit_toggle.get_active());
+
+            {
+                let collection = collection.borrow();
+
+                let changed = |index: usize, chosen: &Option<(String, Rc<Graphic>)>| match *chosen {
+                    Some((_, ref graphic)) => *collection.graphics[index] != **graphic,
+                    None => true
+                };
+
+                if changed(start_index, &start) || changed(target_index, &target) {
+                    needs_change.set(true);
+                }
+
+                *shown.borrow_mut() = collection.search(&search.get_text().unwrap_or_else(String::new));
+            }
+
+            canvas.queue_draw();
+            filmstrip.queue_draw();
+            gallery.queue_draw();
+            matrix.queue_draw();
+
+            gtk::Continue(true)
+        });
+    }
+
+    canvas.connect_draw(move |canvas, ctx| {
+        let gtk::Allocation {width, height, ..} = canvas.get_allocation();
+
+        let chosen = (start_chooser.active_index(), target_chooser.active_index());
+
+        // keep drawing the last morph while no graphics are chosen
+        if let (Some(start), Some(target)) = chosen {
+            if needs_change.get() {
+                let start = collection.borrow().graphics[start].clone();
+                let target = collection.borrow().graphics[target].clone();
+                let subdivision = chosen_subdivision(&subdivision_chooser);
+                *morph_data.borrow_mut() = MorphGraphic::with_subdivision(start, target, subdivision);
+                needs_change.set(false);
+                filmstrip.queue_draw();
+            }
+        }
+
+        let (ref morph, ref groups) = *morph_data.borrow();
+        let fit = chosen_fit(&fit_chooser);
+
+        let (width, height) = (width as f64, height as f64);
+        let affine = view.borrow().transform(width, height, &fit.morph_frame(morph));
+        transform.set(affine);
+
+        let mut renderer = CairoRenderer::new(ctx);
+        renderer.transform(&affine);
+
+        let mut overlay = Overlay::default();
+
+        if grid_toggle.get_active() {
+            overlay = overlay.grid(GRID_SPACING);
+        }
+
+        if polar_toggle.get_active() {
+            overlay = overlay.polar(GRID_SPACING, angle_step.get_value());
+        }
+
+        if frame_toggle.get_active() {
+            overlay = overlay.frame();
+        }
+
+        // cover the whole canvas, which shows more than the frame if zoomed out
+        if let Some(inverse) = affine.invert() {
+            let visible = BoundingBox::new(inverse.apply(Point::new(0.0, 0.0)),
+                inverse.apply(Point::new(width, height)));
+            overlay.draw(&mut renderer, &visible, 1.0 / affine.scale_factor());
+        }
+
+        // while editing, the start graphic is shown as it is being dragged around
+        if let Some((_, ref editor)) = *editor.borrow() {
+            editor.graphic().draw(&mut renderer);
+            render::edit_handles(&mut renderer, editor, grabbed.get(), 1.0 / affine.scale_factor());
+            return gtk::Inhibit(false);
+        }
+
+        let t = scale.get_value();
+
+        if onion_toggle.get_active() {
+            render::onion_skin(&mut renderer, morph, groups, t, onion_count.get_value() as usize, ONION_SPACING);
+        }
+
+        morph.draw_frame(&mut renderer, groups, t);
+
+        if debug_toggle.get_active() {
+            let pixel = 1.0 / affine.scale_factor();
+
+            if t == 0.0 {
+                render::debug_graphic(&mut renderer, &morph.start, pixel);
+            } else if t == 1.0 {
+                render::debug_graphic(&mut renderer, &morph.target, pixel);
+            } else {
+                render::debug_morph(&mut renderer, morph, groups, t, pixel);
+            }
+        }
+
+        if pairs_toggle.get_active() {
+            render::debug_pairs(&mut renderer, morph, groups, t, 1.0 / affine.scale_factor());
+        }
+
+        gtk::Inhibit(false)
+    });
+
+    window.show_all();
+    window.maximize();
+    gtk::main();
+
+    EXIT_SUCCESS
+}
@@ -0,0 +1,33 @@
+use std::env;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use phint::graphics::Graphic;
+
+pub const SEED: u32 = 0x113ba7bb;
+
+pub fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Whether the expectations should be overwritten with the current output.
+pub fn bless() -> bool {
+    env::var_os("PHINT_BLESS").is_some()
+}
+
+pub fn graphics() -> Vec<(String, Rc<Graphic>)> {
+    let mut graphics = ::phint::scan(root().join("data")).unwrap().into_iter()
+        .map(|entry| (entry.name, Rc::new(entry.graphic.unwrap())))
+        .collect::<Vec<_>>();
+
+    graphics.sort_by(|a, b| a.0.cmp(&b.0));
+    graphics
+}
+
+pub fn assert_all(results: Vec<Result<(), String>>) {
+    let failures = results.into_iter()
+        .filter_map(Result::err)
+        .collect::<Vec<_>>();
+
+    assert!(failures.is_empty(), "outputs differ:\n{}", failures.join("\n"));
+}
@@ -0,0 +1,75 @@
+extern crate phint;
+
+mod common;
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+
+use phint::graphics::{MorphGraphic, Subdivision};
+use phint::render::Recorder;
+use common::{SEED, root, bless, graphics, assert_all};
+
+const STEPS: &[f64] = &[0.25, 0.5, 0.75];
+
+/// Compares the output with the expectation in `tests/golden`. Setting
+/// `PHINT_BLESS` overwrites the expectation instead.
+fn compare(name: &str, actual: &str) -> Result<(), String> {
+    let path = root().join("tests").join("golden").join(format!("{}.txt", name));
+
+    if bless() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(&path).and_then(|mut file| file.write_all(actual.as_bytes())).unwrap();
+        return Ok(());
+    }
+
+    let mut expected = String::new();
+    File::open(&path)
+        .and_then(|mut file| file.read_to_string(&mut expected))
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    if expected == actual {
+        return Ok(());
+    }
+
+    let line = expected.lines().zip(actual.lines())
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+
+    Err(format!("{}:{}: expected `{}`, got `{}`", path.display(), line + 1,
+        expected.lines().nth(line).unwrap_or(""), actual.lines().nth(line).unwrap_or("")))
+}
+
+#[test]
+fn draw_graphics() {
+    let results = graphics().into_iter().map(|(name, graphic)| {
+        let mut recorder = Recorder::new();
+        graphic.draw(&mut recorder);
+        compare(&name, &recorder.to_string())
+    }).collect();
+
+    assert_all(results);
+}
+
+#[test]
+fn draw_morphs() {
+    let graphics = graphics();
+    // morph every graphic into the next one, wrapping around at the end
+    let pairs = graphics.iter().zip(graphics.iter().cycle().skip(1));
+
+    let results = pairs.map(|(&(ref start_name, ref start), &(ref target_name, ref target))| {
+        let mut rng = MorphGraphic::seeded_rng(SEED);
+        let (morph, groups) = MorphGraphic::with_rng(start.clone(), target.clone(),
+            Subdivision::default(), &mut rng);
+        let mut output = String::new();
+
+        for &t in STEPS {
+            let mut recorder = Recorder::new();
+            morph.draw(&mut recorder, groups.link(&morph), t);
+            output.push_str(&format!("# t = {}\n{}", t, recorder));
+        }
+
+        compare(&format!("{}-{}", start_name, target_name), &output)
+    }).collect();
+
+    assert_all(results);
+}
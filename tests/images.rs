@@ -0,0 +1,144 @@
+#![cfg(feature = "cairo-rs")]
+
+extern crate cairo;
+extern crate phint;
+
+mod common;
+
+use std::fs::{self, File};
+use std::path::Path;
+use cairo::{Context, Format, ImageSurface};
+
+use phint::graphics::{MorphGraphic, Subdivision};
+use phint::render::{Fit, render_image};
+use common::{SEED, root, bless, graphics, assert_all};
+
+const SIZE: i32 = 128;
+/// Largest difference of a channel which is still accepted, to allow for
+/// antialiasing which varies between cairo versions
+const TOLERANCE: u8 = 16;
+/// Times at which every morph is rendered
+const TIMES: [f64; 3] = [0.25, 0.5, 0.75];
+
+fn read_png(path: &Path) -> Result<ImageSurface, String> {
+    File::open(path)
+        .map_err(|err| err.to_string())
+        .and_then(|mut file| ImageSurface::create_from_png(&mut file).map_err(|err| format!("{:?}", err)))
+        .map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn write_png(path: &Path, image: &ImageSurface) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut file = File::create(path).unwrap();
+    image.write_to_png(&mut file).unwrap();
+}
+
+/// Returns the positions of the pixels which differ by more than the tolerance.
+fn mismatches(expected: &mut ImageSurface, actual: &mut ImageSurface) -> Vec<(i32, i32)> {
+    let (width, height) = (actual.get_width(), actual.get_height());
+    let (expected_stride, actual_stride) = (expected.get_stride(), actual.get_stride());
+    let expected = expected.get_data().unwrap();
+    let actual = actual.get_data().unwrap();
+    let mut mismatches = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            let expected = &expected[(y * expected_stride + x * 4) as usize..][..4];
+            let actual = &actual[(y * actual_stride + x * 4) as usize..][..4];
+
+            let differs = expected.iter().zip(actual)
+                .any(|(&expected, &actual)| expected.max(actual) - expected.min(actual) > TOLERANCE);
+
+            if differs {
+                mismatches.push((x, y));
+            }
+        }
+    }
+
+    mismatches
+}
+
+/// Shows the reference faded out, with the differing pixels marked in red.
+fn diff_image(expected: &ImageSurface, mismatches: &[(i32, i32)]) -> ImageSurface {
+    let diff = ImageSurface::create(Format::ARgb32, expected.get_width(), expected.get_height());
+
+    {
+        let ctx = Context::new(&diff);
+        ctx.set_source_rgb(1.0, 1.0, 1.0);
+        ctx.paint();
+        ctx.set_source_surface(expected, 0.0, 0.0);
+        ctx.paint_with_alpha(0.25);
+
+        ctx.set_source_rgb(1.0, 0.0, 0.0);
+        for &(x, y) in mismatches {
+            ctx.rectangle(x as f64, y as f64, 1.0, 1.0);
+        }
+        ctx.fill();
+    }
+
+    diff
+}
+
+/// Compares the image with the reference in `tests/images`. On failure, the
+/// image and a diff are written to `target/images`. Setting `PHINT_BLESS`
+/// overwrites the reference instead.
+fn compare(name: &str, mut actual: ImageSurface) -> Result<(), String> {
+    let path = root().join("tests").join("images").join(format!("{}.png", name));
+
+    if bless() {
+        write_png(&path, &actual);
+        return Ok(());
+    }
+
+    let mut expected = read_png(&path)?;
+
+    if (expected.get_width(), expected.get_height()) != (actual.get_width(), actual.get_height()) {
+        return Err(format!("{}: expected a size of {}x{}, got {}x{}", path.display(),
+            expected.get_width(), expected.get_height(), actual.get_width(), actual.get_height()));
+    }
+
+    let mismatches = mismatches(&mut expected, &mut actual);
+
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    let output = root().join("target").join("images");
+    write_png(&output.join(format!("{}.actual.png", name)), &actual);
+    write_png(&output.join(format!("{}.diff.png", name)), &diff_image(&expected, &mismatches));
+
+    Err(format!("{}: {} pixels differ, see {}", path.display(), mismatches.len(),
+        output.join(format!("{}.diff.png", name)).display()))
+}
+
+#[test]
+fn render_graphics() {
+    let results = graphics().into_iter().map(|(name, graphic)| {
+        let frame = Fit::Viewbox.graphic_frame(&graphic);
+        let image = render_image(SIZE, SIZE, &frame, |renderer| graphic.draw(renderer));
+        compare(&name, image)
+    }).collect();
+
+    assert_all(results);
+}
+
+#[test]
+fn render_morphs() {
+    let graphics = graphics();
+    // morph every graphic into the next one, wrapping around at the end
+    let pairs = graphics.iter().zip(graphics.iter().cycle().skip(1));
+
+    let results = pairs.flat_map(|(&(ref start_name, ref start), &(ref target_name, ref target))| {
+        let mut rng = MorphGraphic::seeded_rng(SEED);
+        let (morph, groups) = MorphGraphic::with_rng(start.clone(), target.clone(),
+            Subdivision::default(), &mut rng);
+        let frame = Fit::Viewbox.morph_frame(&morph);
+
+        TIMES.iter().map(|&t| {
+            let image = render_image(SIZE, SIZE, &frame, |renderer| morph.draw(renderer, groups.link(&morph), t));
+            compare(&format!("{}-{}-{}", start_name, target_name, t), image)
+        }).collect::<Vec<_>>()
+    }).collect();
+
+    assert_all(results);
+}